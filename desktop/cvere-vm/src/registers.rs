@@ -31,42 +31,162 @@ impl PrivilegeLevel {
     }
 }
 
+/// Maximum depth of banked trap frames (see `TrapFrame`). Taking a trap
+/// while this many are already banked raises a double fault instead of
+/// pushing a ninth.
+const MAX_TRAP_DEPTH: usize = 8;
+
+/// Exception code used when a trap is taken with the trap-frame stack
+/// already full - the processor has nowhere left to bank the interrupted
+/// state, so it gives up on that rather than growing the stack further.
+/// Always dispatched to `exception_handler`, never vectored or delegated.
+pub const EXCEPTION_DOUBLE_FAULT: u16 = 0xFF;
+
+/// Number of entries in `exception_vectors`, following the m68k
+/// exception-vector model (one slot per named standard exception, plus
+/// headroom for game-defined codes).
+const TRAP_VECTOR_COUNT: usize = 16;
+
+// Standard exception codes, m68k-style.
+pub const EXC_ILLEGAL_INSTRUCTION: u16 = 0x00;
+pub const EXC_DIVIDE_BY_ZERO: u16 = 0x01;
+pub const EXC_PRIVILEGE_VIOLATION: u16 = 0x02;
+pub const EXC_ADDRESS_ERROR: u16 = 0x03;
+pub const EXC_BUS_ERROR: u16 = 0x04;
+pub const EXC_TRAP: u16 = 0x05;
+pub const EXC_TRACE: u16 = 0x06;
+
+/// Bits of `sr` holding the current interrupt priority level (IPL), an
+/// m68k-style `IntMask`: an IRQ is only accepted while its own priority is
+/// strictly greater than this. Sits between the low status-flag nibble
+/// (bits 0-3) and the banked exception code in the top byte (bits 8-15).
+const IPL_SHIFT: u16 = 4;
+const IPL_MASK: u16 = 0x7 << IPL_SHIFT;
+
+/// m68k-style trace bit: while set, `should_trace` tells the execution
+/// loop to raise `EXC_TRACE` after retiring each instruction. Bit 7 sits
+/// just below the banked exception code in the top byte and above the
+/// IPL field, the last unused bit in the low byte of `sr`.
+const TRACE_BIT: u16 = 1 << 7;
+
+/// Bits of `sr` that a plain `set_flags` call is allowed to touch - just
+/// the ALU condition codes. The trace bit is excluded on purpose: it can
+/// only be written through the privilege-gated `set_trace`.
+const ZNCV_MASK: u16 = 0x000F;
+
+/// Outcome of `raise_interrupt`, replacing a bare accepted/rejected bool
+/// so callers can tell a masked IRQ (never delivered) from one merely
+/// deferred behind a higher-or-equal-priority handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptOutcome {
+    /// Delivered immediately; the IPL was raised to `level`.
+    Accepted { level: u8 },
+    /// Priority too low for the current IPL (or interrupts globally
+    /// disabled); latched in `pending_irqs` for re-evaluation once the
+    /// IPL drops.
+    Pended,
+    /// This IRQ line is masked off in `interrupt_mask`; not latched.
+    Masked,
+}
+
+/// Maximum number of programmable memory protection regions.
+const MAX_PROTECTION_REGIONS: usize = 8;
+
+/// One PMP-style memory protection region (modeled on RISC-V
+/// `pmpcfg`/`pmpaddr`): a `[base, limit]` address window, the minimum
+/// privilege allowed to touch it, and which operations are permitted.
+/// `check_protection` scans the table in order and applies the first
+/// region that contains the address - a narrower region can carve an
+/// exception out of a broader one by coming before it in the table.
+#[derive(Debug, Clone, Copy)]
+pub struct ProtectionRegion {
+    pub base: u16,
+    pub limit: u16,
+    pub min_privilege: PrivilegeLevel,
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+/// One banked trap frame, pushed by `raise_exception`/`raise_interrupt`
+/// and popped by `return_from_exception`. Modeled on the mstatus
+/// MPIE/SPIE scheme: taking a trap shifts the live pc/sr/privilege (plus
+/// whether interrupts were enabled) onto this stack rather than a single
+/// saved-state slot, so a second trap taken from inside the handler banks
+/// its own frame instead of clobbering the first one's.
+#[derive(Debug, Clone, Copy)]
+struct TrapFrame {
+    pc: u16,
+    sr: u16,
+    privilege: PrivilegeLevel,
+    interrupts_enabled: bool,
+    /// Resolved `vector_base + code * 2` address this trap dispatched
+    /// through, when taken in vectored mode; `None` for single-handler
+    /// dispatch, interrupts, and double faults.
+    vector_address: Option<u16>,
+}
+
 /// Register file with complete privilege and protection
 pub struct RegisterFile {
     // General purpose registers R0-RF
     gp_regs: [u16; 16],
-    
+
     // Special registers
     pub pc: u16,    // Program Counter
     pub sp: u16,    // Stack Pointer (current)
     pub lr: u16,    // Link Register
     pub sr: u16,    // Status Register
-    
+
     // Privilege mode stack pointers (banked)
     pub kernel_sp: u16,      // Ring 0 stack
     pub supervisor_sp: u16,  // Ring 1 stack
     pub user_sp: u16,        // Ring 2 stack
     pub privilege: PrivilegeLevel,
-    
+
     // Exception and interrupt handling
-    pub exception_handler: u16,   // Exception vector
-    pub interrupt_handler: u16,   // Interrupt vector
-    pub saved_pc: u16,            // Saved PC on exception
-    pub saved_sr: u16,            // Saved SR on exception
-    pub saved_privilege: PrivilegeLevel,  // Saved privilege level
-    
-    // Protection and segmentation
-    pub code_base: u16,      // Code segment base
-    pub code_limit: u16,     // Code segment limit
-    pub data_base: u16,      // Data segment base
-    pub data_limit: u16,     // Data segment limit
-    pub stack_base: u16,     // Stack segment base
-    pub stack_limit: u16,    // Stack segment limit
-    
+    pub exception_handler: u16,   // Exception vector (kernel)
+    pub interrupt_handler: u16,   // Interrupt vector (kernel)
+    pub supervisor_exception_handler: u16,  // Exception vector (delegated)
+    pub supervisor_interrupt_handler: u16,  // Interrupt vector (delegated)
+    /// Bit `n` set means exception code `n` is handled in supervisor mode
+    /// instead of kernel mode, the way `medeleg` routes a RISC-V exception
+    /// to S-mode - but only when it traps from below kernel privilege; a
+    /// trap already running in kernel mode always stays in kernel mode.
+    pub exception_delegate: u16,
+    /// Same as `exception_delegate`, for interrupts (`mideleg`-style).
+    pub interrupt_delegate: u16,
+
+    /// When set, `raise_exception` dispatches through `exception_vectors`
+    /// instead of the single `exception_handler`; clear to fall back to
+    /// the old single-handler behavior.
+    pub vectored: bool,
+    /// Base address used only to report the vector's address for
+    /// debugging (see `TrapFrame::vector_address`); the actual handler
+    /// pointer lives in `exception_vectors`, indexed directly by code
+    /// since this crate has no separate addressable memory to read it
+    /// from.
+    pub vector_base: u16,
+    /// Per-exception-code handler table, indexed by exception code
+    /// modulo `TRAP_VECTOR_COUNT`.
+    pub exception_vectors: [u16; TRAP_VECTOR_COUNT],
+
+    trap_stack: Vec<TrapFrame>,   // Banked pc/sr/privilege per nested trap
+
+    // Protection and segmentation (see `ProtectionRegion`)
+    pub protection_regions: [Option<ProtectionRegion>; MAX_PROTECTION_REGIONS],
+
     // Interrupt enable flags
     pub interrupts_enabled: bool,
     pub interrupt_mask: u16,
-    
+
+    // Priority-based interrupt levels (see `InterruptOutcome`)
+    /// Priority (0-7) of each of the 16 IRQ lines; higher preempts lower.
+    pub irq_priority: [u8; 16],
+    /// IRQ lines that passed the mask check in `raise_interrupt` but were
+    /// blocked by the current IPL (or by interrupts being disabled),
+    /// latched here for re-evaluation on IPL drop.
+    pub pending_irqs: u16,
+
     // Console for syscalls
     pub console: Console,
 }
@@ -90,21 +210,64 @@ impl RegisterFile {
             // Exception handlers
             exception_handler: 0x0010,
             interrupt_handler: 0x0020,
-            saved_pc: 0,
-            saved_sr: 0,
-            saved_privilege: PrivilegeLevel::Kernel,
-            
-            // Segment registers (default: full access)
-            code_base: 0x0000,
-            code_limit: 0xFFFF,
-            data_base: 0x0000,
-            data_limit: 0xFFFF,
-            stack_base: 0xD000,
-            stack_limit: 0xFFFF,
-            
+            supervisor_exception_handler: 0x0030,
+            supervisor_interrupt_handler: 0x0040,
+            exception_delegate: 0,  // nothing delegated by default
+            interrupt_delegate: 0,
+
+            vectored: false,
+            vector_base: 0x0100,
+            exception_vectors: [0; TRAP_VECTOR_COUNT],
+
+            trap_stack: Vec::new(),
+
+            // Default region table: reproduces the old hardcoded windows
+            // (kernel-low read-only, I/O kernel-only, user heap, the rest
+            // supervisor-and-up) as PMP-style entries, scanned in order.
+            protection_regions: [
+                Some(ProtectionRegion {
+                    base: 0x0000,
+                    limit: 0x0FFF,
+                    min_privilege: PrivilegeLevel::User,
+                    read: true,
+                    write: false,
+                    execute: true,
+                }),
+                Some(ProtectionRegion {
+                    base: 0xF000,
+                    limit: 0xFFFF,
+                    min_privilege: PrivilegeLevel::Kernel,
+                    read: true,
+                    write: true,
+                    execute: true,
+                }),
+                Some(ProtectionRegion {
+                    base: 0x8000,
+                    limit: 0xDFFF,
+                    min_privilege: PrivilegeLevel::User,
+                    read: true,
+                    write: true,
+                    execute: true,
+                }),
+                Some(ProtectionRegion {
+                    base: 0x1000,
+                    limit: 0xFFFE,
+                    min_privilege: PrivilegeLevel::Supervisor,
+                    read: true,
+                    write: true,
+                    execute: true,
+                }),
+                None,
+                None,
+                None,
+                None,
+            ],
+
             interrupts_enabled: true,
             interrupt_mask: 0xFFFF,
-            
+            irq_priority: [0; 16],
+            pending_irqs: 0,
+
             console: Console::new(),
         }
     }
@@ -218,133 +381,279 @@ impl RegisterFile {
     // EXCEPTION AND INTERRUPT HANDLING
     // ========================================================================
     
-    /// Trigger exception (always escalates to kernel)
+    /// Bank the live pc/sr/privilege/interrupts_enabled as a new `TrapFrame`
+    /// and disable interrupts, the way mstatus shifts the current
+    /// interrupt-enable bit into MPIE/SPIE on trap entry. Shared by
+    /// `raise_exception` and `raise_interrupt` so neither can forget the
+    /// other half of the save/disable pair.
+    fn push_trap_frame(&mut self, vector_address: Option<u16>) {
+        self.trap_stack.push(TrapFrame {
+            pc: self.pc,
+            sr: self.sr,
+            privilege: self.privilege,
+            interrupts_enabled: self.interrupts_enabled,
+            vector_address,
+        });
+        self.interrupts_enabled = false;
+    }
+
+    /// Shared trap-entry sequence: bank state via `push_trap_frame` (or,
+    /// if `trap_stack` is already `MAX_TRAP_DEPTH` deep, give up on
+    /// banking it and raise a double fault instead of pushing a ninth
+    /// frame), then escalate to `target_privilege` and jump to `handler`.
+    ///
+    /// `target_privilege` is `Kernel` unless `delegate_mask` has `code`'s
+    /// bit set and the trap is taken from below kernel privilege - a trap
+    /// already running in kernel mode is never delegated down to
+    /// supervisor, and a double fault always goes to the kernel vector
+    /// regardless of delegation.
+    fn enter_trap(
+        &mut self,
+        code: u16,
+        kernel_handler: u16,
+        delegate_mask: u16,
+        supervisor_handler: u16,
+        vector_address: Option<u16>,
+    ) {
+        let double_fault = self.trap_stack.len() >= MAX_TRAP_DEPTH;
+
+        let (code, target_privilege, handler, vector_address) = if double_fault {
+            (EXCEPTION_DOUBLE_FAULT, PrivilegeLevel::Kernel, self.exception_handler, None)
+        } else if self.privilege != PrivilegeLevel::Kernel && (delegate_mask & (1 << code)) != 0 {
+            (code, PrivilegeLevel::Supervisor, supervisor_handler, vector_address)
+        } else {
+            (code, PrivilegeLevel::Kernel, kernel_handler, vector_address)
+        };
+
+        if !double_fault {
+            self.push_trap_frame(vector_address);
+        }
+
+        // Set exception/IRQ code in SR
+        self.sr = (self.sr & 0x00FF) | (code << 8);
+
+        // Clear the trace bit on entry so the trace handler itself
+        // doesn't immediately retrigger EXC_TRACE; `return_from_exception`
+        // restores it from the banked frame's `sr`.
+        self.sr &= !TRACE_BIT;
+
+        // A trap taken while already at `target_privilege` (a nested trap
+        // in the kernel handler, most commonly) continues on the live
+        // stack rather than reloading the bank - reloading would reset sp
+        // to the bank's base and silently trash the interrupted handler's
+        // stack, which has no frame of its own to recover it from.
+        if self.privilege != target_privilege {
+            // Save current SP
+            match self.privilege {
+                PrivilegeLevel::Supervisor => self.supervisor_sp = self.sp,
+                PrivilegeLevel::User => self.user_sp = self.sp,
+                _ => {}
+            }
+
+            self.privilege = target_privilege;
+            self.sp = match target_privilege {
+                PrivilegeLevel::Kernel => self.kernel_sp,
+                PrivilegeLevel::Supervisor => self.supervisor_sp,
+                PrivilegeLevel::User => self.user_sp, // traps never target user mode
+            };
+        }
+        self.pc = handler;
+    }
+
+    /// Trigger exception (escalates to kernel, unless delegated to supervisor)
     pub fn raise_exception(&mut self, exception_code: u16) {
-        // Save state
-        self.saved_pc = self.pc;
-        self.saved_sr = self.sr;
-        self.saved_privilege = self.privilege;
-        
-        // Set exception code in SR
-        self.sr = (self.sr & 0x00FF) | (exception_code << 8);
-        
-        // Save current SP
-        match self.privilege {
-            PrivilegeLevel::Supervisor => self.supervisor_sp = self.sp,
-            PrivilegeLevel::User => self.user_sp = self.sp,
-            _ => {}
+        let (kernel_handler, vector_address) = if self.vectored {
+            let slot = exception_code as usize % TRAP_VECTOR_COUNT;
+            let address = self.vector_base.wrapping_add(exception_code.wrapping_mul(2));
+            (self.exception_vectors[slot], Some(address))
+        } else {
+            (self.exception_handler, None)
+        };
+        let supervisor_handler = self.supervisor_exception_handler;
+        let delegate_mask = self.exception_delegate;
+        self.enter_trap(exception_code, kernel_handler, delegate_mask, supervisor_handler, vector_address);
+    }
+
+    /// Current interrupt priority level (IPL): an IRQ is only accepted
+    /// while its own `irq_priority` is strictly greater than this.
+    pub fn ipl(&self) -> u8 {
+        ((self.sr & IPL_MASK) >> IPL_SHIFT) as u8
+    }
+
+    fn set_ipl(&mut self, level: u8) {
+        self.sr = (self.sr & !IPL_MASK) | (((level & 0x7) as u16) << IPL_SHIFT);
+    }
+
+    /// Handle interrupt: masked off entirely, pended behind a
+    /// higher-or-equal priority handler, or accepted and dispatched
+    /// (escalating to kernel, unless delegated to supervisor). On
+    /// acceptance the IPL is raised to this IRQ's priority so that
+    /// equal or lower-priority IRQs stay pending until it drops again.
+    pub fn raise_interrupt(&mut self, irq: u16) -> InterruptOutcome {
+        if (self.interrupt_mask & (1 << irq)) == 0 {
+            return InterruptOutcome::Masked;
         }
-        
-        // Enter kernel mode and jump to handler
-        self.privilege = PrivilegeLevel::Kernel;
-        self.sp = self.kernel_sp;
-        self.pc = self.exception_handler;
+
+        let priority = self.irq_priority[irq as usize & 0xF];
+        if !self.interrupts_enabled || priority <= self.ipl() {
+            self.pending_irqs |= 1 << irq;
+            return InterruptOutcome::Pended;
+        }
+
+        self.pending_irqs &= !(1 << irq);
+        let kernel_handler = self.interrupt_handler;
+        let supervisor_handler = self.supervisor_interrupt_handler;
+        let delegate_mask = self.interrupt_delegate;
+        self.enter_trap(irq, kernel_handler, delegate_mask, supervisor_handler, None);
+        self.set_ipl(priority);
+        InterruptOutcome::Accepted { level: priority }
     }
-    
-    /// Handle interrupt (escalates to kernel if enabled)
-    pub fn raise_interrupt(&mut self, irq: u16) -> bool {
-        if !self.interrupts_enabled || (self.interrupt_mask & (1 << irq)) == 0 {
-            return false; // Interrupt masked
+
+    /// Re-evaluate `pending_irqs` against the current IPL, highest
+    /// priority first, and accept the first one that now clears it.
+    /// Called after `return_from_exception` lowers the IPL back down.
+    fn dispatch_pending_irqs(&mut self) {
+        if self.pending_irqs == 0 || !self.interrupts_enabled {
+            return;
         }
-        
-        // Save state
-        self.saved_pc = self.pc;
-        self.saved_sr = self.sr;
-        self.saved_privilege = self.privilege;
-        
-        // Set IRQ number in SR
-        self.sr = (self.sr & 0x00FF) | (irq << 8);
-        
-        // Save current SP and enter kernel
-        match self.privilege {
-            PrivilegeLevel::Supervisor => self.supervisor_sp = self.sp,
-            PrivilegeLevel::User => self.user_sp = self.sp,
-            _ => {}
+
+        let mut candidates: Vec<u16> = (0..16).filter(|irq| self.pending_irqs & (1 << irq) != 0).collect();
+        candidates.sort_by_key(|&irq| std::cmp::Reverse(self.irq_priority[irq as usize]));
+
+        for irq in candidates {
+            if self.irq_priority[irq as usize] > self.ipl() {
+                self.raise_interrupt(irq);
+                return;
+            }
         }
-        
-        self.privilege = PrivilegeLevel::Kernel;
-        self.sp = self.kernel_sp;
-        self.pc = self.interrupt_handler;
-        
-        true
     }
-    
-    /// Return from exception/interrupt (RETI instruction)
+
+    /// Return from exception/interrupt (RETI instruction). Valid from
+    /// kernel mode (the usual case) or supervisor mode (a delegated
+    /// trap's handler returning) - never from user mode, since traps
+    /// never hand control to user mode in the first place.
     pub fn return_from_exception(&mut self) -> Result<(), String> {
-        if self.privilege != PrivilegeLevel::Kernel {
-            return Err("RETI can only be called from kernel mode".to_string());
+        if self.privilege == PrivilegeLevel::User {
+            return Err("RETI cannot be called from user mode".to_string());
         }
-        
+
+        let frame = self
+            .trap_stack
+            .pop()
+            .ok_or_else(|| "RETI with no banked trap frame (trap stack underflow)".to_string())?;
+
         // Restore state
-        self.pc = self.saved_pc;
-        self.sr = self.saved_sr;
-        
-        // Save kernel SP
-        self.kernel_sp = self.sp;
-        
+        self.pc = frame.pc;
+        self.sr = frame.sr;
+        self.interrupts_enabled = frame.interrupts_enabled;
+
+        // Bank the handler's SP back to whichever level was running it
+        match self.privilege {
+            PrivilegeLevel::Kernel => self.kernel_sp = self.sp,
+            PrivilegeLevel::Supervisor => self.supervisor_sp = self.sp,
+            PrivilegeLevel::User => {}
+        }
+
         // Restore privilege level and SP
-        self.privilege = self.saved_privilege;
+        self.privilege = frame.privilege;
         self.sp = match self.privilege {
             PrivilegeLevel::Kernel => self.kernel_sp,
             PrivilegeLevel::Supervisor => self.supervisor_sp,
             PrivilegeLevel::User => self.user_sp,
         };
-        
+
+        self.dispatch_pending_irqs();
+
         Ok(())
     }
+
+    /// Current nesting depth of banked trap frames.
+    pub fn trap_depth(&self) -> usize {
+        self.trap_stack.len()
+    }
+
+    /// Vector address the innermost banked trap dispatched through, if
+    /// it was taken in vectored mode - lets debugging tools report which
+    /// vector fired without having to re-derive it from `sr`.
+    pub fn current_vector_address(&self) -> Option<u16> {
+        self.trap_stack.last().and_then(|frame| frame.vector_address)
+    }
     
     // ========================================================================
     // PROTECTION CHECKS
     // ========================================================================
     
-    /// Check if current privilege can access memory address
-    pub fn can_access_memory(&self, address: u16, write: bool) -> Result<(), String> {
-        // Kernel can access everything
+    /// Find the first programmed region that contains `address`, scanning
+    /// in table order (index 0 first).
+    fn region_for(&self, address: u16) -> Option<&ProtectionRegion> {
+        self.protection_regions.iter().flatten().find(|region| address >= region.base && address <= region.limit)
+    }
+
+    /// Shared PMP-style check backing both `can_access_memory` and
+    /// `can_execute`: kernel bypasses everything; anyone else must land
+    /// inside a programmed region whose `min_privilege` they satisfy and
+    /// whose permission bit for the requested operation is set.
+    fn check_protection(&self, address: u16, read: bool, write: bool, execute: bool) -> Result<(), String> {
         if self.privilege == PrivilegeLevel::Kernel {
             return Ok(());
         }
-        
-        // Check kernel memory protection (0x0000-0x0FFF)
-        if address < 0x1000 {
-            if write {
-                return Err(format!("Access violation: Cannot write to kernel memory at 0x{:04X}", address));
-            }
-            // Read-only access allowed
-            return Ok(());
-        }
-        
-        // Check I/O memory (0xF000-0xFFFF) - kernel only
-        if address >= 0xF000 {
-            return Err(format!("Access violation: Cannot access I/O memory at 0x{:04X}", address));
-        }
-        
-        // Supervisor can access game world memory (0x2000-0x7FFF)
-        if self.privilege == PrivilegeLevel::Supervisor {
-            if address >= 0x2000 && address < 0x8000 {
-                return Ok(());
-            }
+
+        let region = self
+            .region_for(address)
+            .ok_or_else(|| format!("Access violation: no protection region covers 0x{:04X}", address))?;
+
+        if (self.privilege as u8) > (region.min_privilege as u8) {
+            return Err(format!(
+                "Access violation: {:?} privilege cannot access 0x{:04X} (requires {:?} or higher)",
+                self.privilege, address, region.min_privilege
+            ));
         }
-        
-        // User mode has restricted access
-        if self.privilege == PrivilegeLevel::User {
-            // Can only access user heap (0x8000-0xDFFF)
-            if address >= 0x8000 && address < 0xE000 {
-                return Ok(());
-            }
-            return Err(format!("Access violation: User mode cannot access 0x{:04X}", address));
+
+        if (read && !region.read) || (write && !region.write) || (execute && !region.execute) {
+            return Err(format!("Access violation: permission denied at 0x{:04X}", address));
         }
-        
+
         Ok(())
     }
-    
+
+    /// Check if current privilege can access memory address
+    pub fn can_access_memory(&self, address: u16, write: bool) -> Result<(), String> {
+        self.check_protection(address, !write, write, false)
+    }
+
     /// Check if can execute instruction at address
     pub fn can_execute(&self, address: u16) -> Result<(), String> {
-        if address < self.code_base || address >= self.code_limit {
-            return Err(format!("Execution violation: PC 0x{:04X} outside code segment", address));
+        self.check_protection(address, false, false, true)
+    }
+
+    /// Reprogram protection region `index` (PMP-style); only callable
+    /// from kernel mode, the way only the reality core should be able to
+    /// re-partition a sandboxed script's address space.
+    pub fn set_protection_region(
+        &mut self,
+        index: usize,
+        base: u16,
+        limit: u16,
+        min_privilege: PrivilegeLevel,
+        read: bool,
+        write: bool,
+        execute: bool,
+    ) -> Result<(), String> {
+        if self.privilege != PrivilegeLevel::Kernel {
+            return Err("Only kernel mode may reprogram protection regions".to_string());
+        }
+        if index >= MAX_PROTECTION_REGIONS {
+            return Err(format!(
+                "Protection region index {} out of range (max {})",
+                index,
+                MAX_PROTECTION_REGIONS - 1
+            ));
         }
+
+        self.protection_regions[index] = Some(ProtectionRegion { base, limit, min_privilege, read, write, execute });
         Ok(())
     }
-    
+
     // ========================================================================
     // PRIVILEGE QUERIES
     // ========================================================================
@@ -374,9 +683,33 @@ impl RegisterFile {
     }
     
     pub fn set_flags(&mut self, flags: StatusFlags) {
-        // Preserve upper bits (exception code)
-        let upper = self.sr & 0xFF00;
-        self.sr = upper | (flags.to_u16() & 0x00FF);
+        // Only the ALU condition codes are writable this way; IPL, the
+        // trace bit, and the banked exception code all live outside
+        // `ZNCV_MASK` and are left untouched.
+        self.sr = (self.sr & !ZNCV_MASK) | (flags.to_u16() & ZNCV_MASK);
+    }
+
+    /// Whether the trace bit is set; the execution loop should consult
+    /// this after retiring each instruction and, if true, raise
+    /// `EXC_TRACE` for single-step debugging.
+    pub fn should_trace(&self) -> bool {
+        self.sr & TRACE_BIT != 0
+    }
+
+    /// Set or clear the trace bit. Requires supervisor or kernel
+    /// privilege, the way only a debugger or the scripting sandbox's
+    /// host (never a sandboxed user-mode script) should be able to
+    /// arm single-stepping.
+    pub fn set_trace(&mut self, enabled: bool) -> Result<(), String> {
+        if self.privilege == PrivilegeLevel::User {
+            return Err("Setting the trace flag requires supervisor or kernel privilege".to_string());
+        }
+        if enabled {
+            self.sr |= TRACE_BIT;
+        } else {
+            self.sr &= !TRACE_BIT;
+        }
+        Ok(())
     }
     
     // ========================================================================
@@ -393,11 +726,10 @@ impl RegisterFile {
         self.lr = 0;
         self.sr = 0;
         self.privilege = PrivilegeLevel::Kernel;
-        self.saved_pc = 0;
-        self.saved_sr = 0;
-        self.saved_privilege = PrivilegeLevel::Kernel;
+        self.trap_stack.clear();
         self.interrupts_enabled = true;
         self.interrupt_mask = 0xFFFF;
+        self.pending_irqs = 0;
     }
     
     pub fn dump(&self) -> String {
@@ -417,9 +749,10 @@ impl RegisterFile {
         result.push_str(&format!("  SR: 0x{:04X} ", self.sr));
         
         let flags = self.get_flags();
-        result.push_str(&format!("[Z={} N={} C={} V={}]\n", 
+        result.push_str(&format!("[Z={} N={} C={} V={} T={} IPL={}]\n",
             flags.zero as u8, flags.negative as u8,
-            flags.carry as u8, flags.overflow as u8));
+            flags.carry as u8, flags.overflow as u8,
+            flags.trace as u8, self.ipl()));
         
         result.push_str(&format!("\nPrivilege: {:?} (Ring {})\n", 
             self.privilege, self.privilege as u8));
@@ -427,14 +760,17 @@ impl RegisterFile {
         result.push_str(&format!("  Supervisor SP: 0x{:04X}\n", self.supervisor_sp));
         result.push_str(&format!("  User SP:       0x{:04X}\n", self.user_sp));
         
-        result.push_str(&format!("\nProtection:\n"));
-        result.push_str(&format!("  Code:  0x{:04X}-0x{:04X}\n", 
-            self.code_base, self.code_limit));
-        result.push_str(&format!("  Data:  0x{:04X}-0x{:04X}\n", 
-            self.data_base, self.data_limit));
-        result.push_str(&format!("  Stack: 0x{:04X}-0x{:04X}\n", 
-            self.stack_base, self.stack_limit));
-        
+        result.push_str(&format!("\nProtection regions:\n"));
+        for (i, region) in self.protection_regions.iter().enumerate() {
+            match region {
+                Some(r) => result.push_str(&format!(
+                    "  [{}] 0x{:04X}-0x{:04X} min={:?} r={} w={} x={}\n",
+                    i, r.base, r.limit, r.min_privilege, r.read as u8, r.write as u8, r.execute as u8
+                )),
+                None => result.push_str(&format!("  [{}] (unused)\n", i)),
+            }
+        }
+
         result
     }
     
@@ -453,6 +789,11 @@ pub struct StatusFlags {
     pub negative: bool,
     pub carry: bool,
     pub overflow: bool,
+    /// m68k-style single-step trace bit. Read-only here for
+    /// introspection via `get_flags`/`dump` - writing it goes through
+    /// `RegisterFile::set_trace`, not `set_flags`, since it requires
+    /// supervisor-or-above privilege.
+    pub trace: bool,
 }
 
 impl StatusFlags {
@@ -462,24 +803,27 @@ impl StatusFlags {
             negative: false,
             carry: false,
             overflow: false,
+            trace: false,
         }
     }
-    
+
     pub fn to_u16(&self) -> u16 {
         let mut sr = 0u16;
         if self.zero { sr |= 1 << 0; }
         if self.negative { sr |= 1 << 1; }
         if self.carry { sr |= 1 << 2; }
         if self.overflow { sr |= 1 << 3; }
+        if self.trace { sr |= TRACE_BIT; }
         sr
     }
-    
+
     pub fn from_u16(sr: u16) -> Self {
         StatusFlags {
             zero: (sr & (1 << 0)) != 0,
             negative: (sr & (1 << 1)) != 0,
             carry: (sr & (1 << 2)) != 0,
             overflow: (sr & (1 << 3)) != 0,
+            trace: (sr & TRACE_BIT) != 0,
         }
     }
 }
\ No newline at end of file
@@ -3,10 +3,13 @@
 // System call handler with game integration for reality reprogramming
 // ============================================================================
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
 
 /// System call numbers - organized by privilege level
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u16)]
 pub enum Syscall {
     // ===== RING 2 (USER MODE) - Available to all code =====
@@ -65,9 +68,16 @@ pub enum Syscall {
     SetQuestFlag = 0x70,   // Set quest flag
     GetQuestFlag = 0x71,   // Get quest flag
     TriggerEvent = 0x72,   // Trigger story event
-    SaveGame = 0x73,       // Save game state
-    LoadGame = 0x74,       // Load game state
-    
+    SaveGame = 0x73,       // Save game state (GameWorld::save_to)
+    LoadGame = 0x74,       // Load game state (GameWorld::load_from)
+
+    // Embedded key-value persistence (0x75-0x7F) - see `KvStore`
+    DbOpen = 0x75,         // Open/select the named table
+    DbPut = 0x76,          // Store key/value in the open table
+    DbGet = 0x77,          // Look up key in the open table
+    DbQueryRange = 0x78,   // Start a prefix query, returns a cursor
+    DbNext = 0x79,         // Advance a query cursor, returns (key, val)
+
     // ===== RING 0 (KERNEL MODE) - Reality manipulation core =====
     // Direct Hardware Access (0x80-0x8F)
     ReadPort = 0x80,       // Read I/O port
@@ -90,8 +100,8 @@ pub enum Syscall {
     RealityCompile = 0xA2, // Compile reality code
     RealityExecute = 0xA3, // Execute reality modification
     RealityRevert = 0xA4,  // Undo reality change
-    RealitySave = 0xA5,    // Save reality state
-    RealityLoad = 0xA6,    // Load reality state
+    RealitySave = 0xA5,    // Save reality state (RealityEngine::save_to)
+    RealityLoad = 0xA6,    // Load reality state (RealityEngine::load_from)
     RealityQuery = 0xA7,   // Query reality properties
     
     // Physics Engine (0xB0-0xBF)
@@ -156,7 +166,12 @@ impl Syscall {
             0x72 => Syscall::TriggerEvent,
             0x73 => Syscall::SaveGame,
             0x74 => Syscall::LoadGame,
-            
+            0x75 => Syscall::DbOpen,
+            0x76 => Syscall::DbPut,
+            0x77 => Syscall::DbGet,
+            0x78 => Syscall::DbQueryRange,
+            0x79 => Syscall::DbNext,
+
             // Kernel mode (Reality)
             0x80 => Syscall::ReadPort,
             0x81 => Syscall::WritePort,
@@ -214,7 +229,9 @@ impl Syscall {
             Syscall::SetPlayerStat | Syscall::AddInventory | 
             Syscall::RemoveInventory | Syscall::ShowDialog |
             Syscall::SetQuestFlag | Syscall::GetQuestFlag | 
-            Syscall::TriggerEvent | Syscall::SaveGame | Syscall::LoadGame
+            Syscall::TriggerEvent | Syscall::SaveGame | Syscall::LoadGame |
+            Syscall::DbOpen | Syscall::DbPut | Syscall::DbGet |
+            Syscall::DbQueryRange | Syscall::DbNext
                 => PrivilegeLevel::Supervisor,
             
             // Kernel mode (Ring 0) - Reality manipulation
@@ -223,6 +240,251 @@ impl Syscall {
     }
 }
 
+// ============================================================================
+// Plugin hook manager
+//
+// Lets host code observe or veto syscalls before they execute, without
+// recompiling guest programs - e.g. a security hook that rejects
+// RealityWrite to protected addresses or rate-limits ModifyMatter, or a
+// gameplay hook that logs quest triggers. Hooks registered for a given
+// syscall run in priority order; the first one to return `Override` or
+// `Cancel` decides the outcome and the rest of the chain doesn't run.
+// ============================================================================
+
+/// Decoded arguments for a single syscall invocation, as seen by a
+/// registered hook before the syscall executes. Arguments are the raw
+/// values a guest program places in the first four general purpose
+/// registers ahead of a `SYSCALL`, this VM's syscall calling convention.
+#[derive(Debug, Clone, Copy)]
+pub struct SyscallArgs {
+    pub syscall: Syscall,
+    pub arg0: u16,
+    pub arg1: u16,
+    pub arg2: u16,
+    pub arg3: u16,
+}
+
+/// What a hook wants to happen to the syscall it just observed.
+pub enum HookResult {
+    /// Let the syscall (or the next hook in the chain) proceed normally.
+    Continue,
+    /// Skip execution and use this value as the syscall's result.
+    Override(u16),
+    /// Skip execution entirely; the syscall returns no result.
+    Cancel,
+}
+
+struct RegisteredHook {
+    priority: i32,
+    handler: Box<dyn Fn(&SyscallArgs) -> HookResult>,
+}
+
+/// Dispatches a syscall through the priority-ordered chain of hooks
+/// registered for it before the executor runs the syscall itself. Lower
+/// `priority` values run first, so security hooks (sandboxing the
+/// dangerous Ring 0 reality syscalls) can be registered ahead of
+/// gameplay hooks and veto a syscall before gameplay code ever sees it.
+pub struct HookManager {
+    hooks: HashMap<Syscall, Vec<RegisteredHook>>,
+}
+
+impl HookManager {
+    pub fn new() -> Self {
+        HookManager { hooks: HashMap::new() }
+    }
+
+    /// Register `handler` for `syscall`, run at `priority` (lower runs
+    /// earlier; hooks registered at equal priority keep registration
+    /// order).
+    pub fn register(
+        &mut self,
+        syscall: Syscall,
+        priority: i32,
+        handler: Box<dyn Fn(&SyscallArgs) -> HookResult>,
+    ) {
+        let chain = self.hooks.entry(syscall).or_insert_with(Vec::new);
+        chain.push(RegisteredHook { priority, handler });
+        chain.sort_by_key(|hook| hook.priority);
+    }
+
+    /// Walk the hook chain registered for `args.syscall` in priority
+    /// order, stopping at the first `Override`/`Cancel`. Returns
+    /// `Continue` if no hook intercepts - including when none are
+    /// registered for this syscall - so the executor's normal handling
+    /// runs unmodified.
+    pub fn dispatch(&self, args: &SyscallArgs) -> HookResult {
+        if let Some(chain) = self.hooks.get(&args.syscall) {
+            for hook in chain {
+                match (hook.handler)(args) {
+                    HookResult::Continue => continue,
+                    other => return other,
+                }
+            }
+        }
+        HookResult::Continue
+    }
+}
+
+// ============================================================================
+// Embedded key-value persistence
+//
+// Backs the `DbOpen`/`DbPut`/`DbGet`/`DbQueryRange`/`DbNext` syscalls so
+// scripts can persist arbitrary structured data - high scores, player
+// progress keyed by id, world-generation seeds - independently of the
+// `GameWorld`/`RealityEngine` snapshot system. This crate has no
+// Cargo.toml to hang a database crate (sled or rusqlite behind a feature
+// flag, as a richer backend eventually could be) off of, but durability
+// itself doesn't need one: `KvStore::open_file` loads its tables from a
+// plain file using the same length-prefixed encoding as `encode_entities`
+// et al., and every `put` flushes the full table set straight back out,
+// so a crash between syscalls loses nothing. Tables are ordered by key
+// so `DbQueryRange` can do a prefix scan, and every operation reports a
+// status code distinguishing "missing key" from "no table open" from an
+// outright error, the way a heavier backend would.
+// ============================================================================
+
+pub const DB_OK: u16 = 0;
+pub const DB_NOT_FOUND: u16 = 1;
+pub const DB_NO_TABLE_OPEN: u16 = 2;
+pub const DB_IO_ERROR: u16 = 3;
+
+/// An open prefix-range query: the matching `(key, value)` pairs still
+/// to be handed out by `DbNext`, in ascending key order.
+struct QueryCursor {
+    remaining: VecDeque<(String, u32)>,
+}
+
+/// In-memory key-value store keyed by table name, each table an ordered
+/// map so range queries come back in key order without re-sorting.
+/// `DbOpen` selects the "current" table that `DbPut`/`DbGet` operate on,
+/// matching the syscalls' (key, val) signatures, which carry no table
+/// name of their own.
+///
+/// Keys arrive here already decoded to `&str`; this crate has no
+/// addressable guest memory to read a null-terminated buffer from (see
+/// the `PrintStr` convention this reuses), so whatever wires register
+/// arguments into these methods is responsible for that read, just as
+/// `GameWorld::pending_timer` leaves its `SetTimer` request to the caller.
+pub struct KvStore {
+    tables: HashMap<String, BTreeMap<String, u32>>,
+    current: Option<String>,
+    cursors: HashMap<u16, QueryCursor>,
+    next_cursor: u16,
+    /// Backing file set by `open_file`; `None` for a pure in-memory store
+    /// (e.g. tests) that never touches disk. `put` flushes here
+    /// immediately after every write when set.
+    path: Option<PathBuf>,
+}
+
+impl KvStore {
+    pub fn new() -> Self {
+        KvStore {
+            tables: HashMap::new(),
+            current: None,
+            cursors: HashMap::new(),
+            next_cursor: 1,
+            path: None,
+        }
+    }
+
+    /// Open a file-backed store, loading any tables already written to
+    /// `path` (a missing file just starts empty - there's nothing to
+    /// load on a fresh save slot). Every `put` afterwards flushes the
+    /// full table set back to `path`, so data survives the process
+    /// exiting uncleanly, not just a clean `flush`.
+    pub fn open_file(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let tables = match fs::read(&path) {
+            Ok(data) => decode_kv_tables(&data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(KvStore { tables, current: None, cursors: HashMap::new(), next_cursor: 1, path: Some(path) })
+    }
+
+    /// Write every table back to the backing file set by `open_file`.
+    /// A no-op for a pure in-memory store. `put` already calls this
+    /// after each write; exposed directly so a caller can force a save
+    /// point (e.g. before a deliberate shutdown) without writing a key.
+    pub fn flush(&self) -> io::Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        fs::write(path, encode_kv_tables(&self.tables))
+    }
+
+    /// Select `name` as the current table, creating it if it doesn't
+    /// exist yet. Backs `DbOpen`.
+    pub fn open(&mut self, name: &str) -> u16 {
+        self.tables.entry(name.to_string()).or_insert_with(BTreeMap::new);
+        self.current = Some(name.to_string());
+        DB_OK
+    }
+
+    /// Store `value` under `key` in the current table and, for a
+    /// file-backed store, flush every table to disk before returning -
+    /// so a crash immediately after `DbPut` still reports success to
+    /// the caller iff the write actually landed. Backs `DbPut`.
+    pub fn put(&mut self, key: &str, value: u32) -> u16 {
+        match self.current.as_ref().and_then(|name| self.tables.get_mut(name)) {
+            Some(table) => {
+                table.insert(key.to_string(), value);
+                if self.flush().is_err() {
+                    return DB_IO_ERROR;
+                }
+                DB_OK
+            }
+            None => DB_NO_TABLE_OPEN,
+        }
+    }
+
+    /// Look up `key` in the current table. Backs `DbGet`; the caller
+    /// distinguishes "not found" from "no table open" via the returned
+    /// status rather than treating both as the same missing value.
+    pub fn get(&self, key: &str) -> (u16, u32) {
+        match self.current.as_ref().and_then(|name| self.tables.get(name)) {
+            Some(table) => match table.get(key) {
+                Some(&value) => (DB_OK, value),
+                None => (DB_NOT_FOUND, 0),
+            },
+            None => (DB_NO_TABLE_OPEN, 0),
+        }
+    }
+
+    /// Start a prefix scan over the current table, returning a cursor id
+    /// to pass to `next`. Backs `DbQueryRange`.
+    pub fn query_range(&mut self, prefix: &str) -> (u16, u16) {
+        let table = match self.current.as_ref().and_then(|name| self.tables.get(name)) {
+            Some(table) => table,
+            None => return (DB_NO_TABLE_OPEN, 0),
+        };
+        let remaining: VecDeque<(String, u32)> = table
+            .range(prefix.to_string()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, &value)| (key.clone(), value))
+            .collect();
+        let cursor_id = self.next_cursor;
+        self.next_cursor += 1;
+        self.cursors.insert(cursor_id, QueryCursor { remaining });
+        (DB_OK, cursor_id)
+    }
+
+    /// Advance `cursor`, returning its next `(key, value)` pair. Returns
+    /// `DB_NOT_FOUND` once the cursor is exhausted (and drops it), or if
+    /// `cursor` doesn't exist. Backs `DbNext`.
+    pub fn next(&mut self, cursor: u16) -> (u16, Option<(String, u32)>) {
+        let Some(state) = self.cursors.get_mut(&cursor) else {
+            return (DB_NOT_FOUND, None);
+        };
+        match state.remaining.pop_front() {
+            Some(entry) => (DB_OK, Some(entry)),
+            None => {
+                self.cursors.remove(&cursor);
+                (DB_NOT_FOUND, None)
+            }
+        }
+    }
+}
+
 use crate::registers::PrivilegeLevel;
 
 /// Console with extended game features
@@ -322,6 +584,133 @@ pub struct Entity {
     pub properties: HashMap<String, u16>,
 }
 
+// ============================================================================
+// Event-bytecode interpreter
+//
+// `TriggerEvent` payloads are compact byte scripts read sequentially from
+// a given offset, in the spirit of classic adventure-engine event
+// tables: story beats become data instead of chains of raw syscalls.
+// ============================================================================
+
+/// Maximum opcodes a single `run_event` invocation will execute, guarding
+/// against a script whose `JUMP_IF_FLAG` loops back on itself forever.
+const EVENT_OPCODE_BUDGET: usize = 1024;
+
+const EV_END: u8 = 0x00;
+const EV_SPAWN: u8 = 0x01;        // entity_type: u16, x: u16, y: u16
+const EV_SET_FLAG: u8 = 0x02;     // flag_id: u16, value: u8
+const EV_GIVE_ITEM: u8 = 0x03;    // item_id: u16
+const EV_SHOW_DIALOG: u8 = 0x04;  // text: null-terminated string
+const EV_JUMP_IF_FLAG: u8 = 0x05; // flag_id: u16, target_offset: u16 (absolute)
+const EV_DELAY: u8 = 0x06;        // cycles: u16
+
+/// Why `GameWorld::run_event` stopped running a script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventExitReason {
+    /// Ran an `EV_END` opcode.
+    Completed,
+    /// Used up `EVENT_OPCODE_BUDGET` without reaching `EV_END`.
+    BudgetExhausted,
+    /// Hit a byte that isn't a recognized opcode, at the given offset.
+    BadOpcode { offset: usize, opcode: u8 },
+}
+
+/// Cursor-based reader over an event script buffer. Out-of-range reads
+/// return 0 (or an empty/truncated string) rather than panicking, so a
+/// malformed or truncated script degrades gracefully instead of
+/// crashing the interpreter.
+struct EventCursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> EventCursor<'a> {
+    fn new(data: &'a [u8], start: usize) -> Self {
+        EventCursor { data, pos: start }
+    }
+
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    fn seek(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    fn read_u8(&mut self) -> u8 {
+        let byte = self.data.get(self.pos).copied().unwrap_or(0);
+        self.pos += 1;
+        byte
+    }
+
+    fn read_u16_le(&mut self) -> u16 {
+        let lo = self.read_u8();
+        let hi = self.read_u8();
+        u16::from_le_bytes([lo, hi])
+    }
+
+    /// Read a null-terminated string, stopping at the buffer's end if no
+    /// terminator is found.
+    fn read_str(&mut self) -> String {
+        let mut bytes = Vec::new();
+        loop {
+            match self.data.get(self.pos) {
+                Some(0) | None => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(&b) => {
+                    bytes.push(b);
+                    self.pos += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+// ============================================================================
+// Delta-compressed entity snapshots (multiplayer sync)
+//
+// Rather than re-sending every entity's full state every frame, each
+// client keeps a "baseline" - its last acknowledged view of the world's
+// entities. `write_delta` diffs the live, relevance-filtered entity set
+// against that baseline and emits, per entity, a changed-field mask plus
+// only the values that changed; a new entity (absent from the baseline)
+// is sent in full, and an entity the baseline still has but the world no
+// longer does is sent as a removal marker. Mirrors the full-pack/delta
+// approach Quake-family servers use to bound bandwidth.
+// ============================================================================
+
+/// Entities farther than this from (`player_x`, `player_y`) are dropped
+/// from delta snapshots entirely.
+const DELTA_RELEVANCE_RADIUS: i32 = 32;
+
+const DELTA_ENTRY_NEW: u8 = 1 << 5;
+const DELTA_ENTRY_REMOVED: u8 = 1 << 6;
+const DELTA_FIELD_TYPE: u8 = 1 << 0;
+const DELTA_FIELD_X: u8 = 1 << 1;
+const DELTA_FIELD_Y: u8 = 1 << 2;
+const DELTA_FIELD_Z: u8 = 1 << 3;
+const DELTA_FIELD_PROPERTIES: u8 = 1 << 4;
+
+/// A client's last-acknowledged snapshot of the world's relevant
+/// entities. `GameWorld::write_delta` diffs against this;
+/// `GameWorld::ack_baseline` replaces it with the world's relevant
+/// entities as they are when it is called. It matches the frame the
+/// client received only if no entity changed between `write_delta` and
+/// `ack_baseline`; the frame that was sent is not kept.
+#[derive(Debug, Clone, Default)]
+pub struct ClientBaseline {
+    entities: HashMap<u16, Entity>,
+}
+
+impl ClientBaseline {
+    pub fn new() -> Self {
+        ClientBaseline { entities: HashMap::new() }
+    }
+}
+
 /// Game world state
 pub struct GameWorld {
     pub entities: HashMap<u16, Entity>,
@@ -332,6 +721,22 @@ pub struct GameWorld {
     pub player_stats: HashMap<u16, u16>,
     pub inventory: Vec<u16>,
     pub next_entity_id: u16,
+
+    /// Dialog text queued by `SHOW_DIALOG` event opcodes, awaiting
+    /// display by whatever presents `ShowDialog` to the player. Not part
+    /// of the save format - transient like `Console::output`, not world
+    /// state.
+    pub pending_dialogs: Vec<String>,
+    /// Timer duration (in cycles) requested by the most recent `DELAY`
+    /// event opcode. There's no syscall dispatcher in this crate to wire
+    /// straight into `SetTimer`, so `run_event` leaves the request here
+    /// for the caller to act on, same as `pending_dialogs`.
+    pub pending_timer: Option<u16>,
+
+    /// Per-client last-acknowledged entity baselines, keyed by client
+    /// id, for `write_delta_for`/`ack_baseline`. Not part of the save
+    /// format - network session state, not world state.
+    pub client_baselines: HashMap<u16, ClientBaseline>,
 }
 
 impl GameWorld {
@@ -345,6 +750,9 @@ impl GameWorld {
             player_stats: HashMap::new(),
             inventory: Vec::new(),
             next_entity_id: 1,
+            pending_dialogs: Vec::new(),
+            pending_timer: None,
+            client_baselines: HashMap::new(),
         }
     }
     
@@ -386,6 +794,402 @@ impl GameWorld {
             self.tiles[y][x] = tile;
         }
     }
+
+    /// Run an event script starting at `start_offset` until it hits
+    /// `EV_END`, runs out of opcode budget, or reads an unrecognized
+    /// opcode. Backs the payload of the `TriggerEvent` syscall: story
+    /// beats can be authored as data instead of chains of raw syscalls.
+    pub fn run_event(&mut self, script: &[u8], start_offset: usize) -> EventExitReason {
+        let mut cursor = EventCursor::new(script, start_offset);
+        let mut budget = EVENT_OPCODE_BUDGET;
+
+        loop {
+            if budget == 0 {
+                return EventExitReason::BudgetExhausted;
+            }
+            budget -= 1;
+
+            let offset = cursor.pos();
+            let opcode = cursor.read_u8();
+            match opcode {
+                EV_END => return EventExitReason::Completed,
+                EV_SPAWN => {
+                    let entity_type = cursor.read_u16_le();
+                    let x = cursor.read_u16_le() as i16;
+                    let y = cursor.read_u16_le() as i16;
+                    self.create_entity(entity_type, x, y);
+                }
+                EV_SET_FLAG => {
+                    let flag_id = cursor.read_u16_le();
+                    let value = cursor.read_u8() != 0;
+                    self.quest_flags.insert(flag_id, value);
+                }
+                EV_GIVE_ITEM => {
+                    let item_id = cursor.read_u16_le();
+                    self.inventory.push(item_id);
+                }
+                EV_SHOW_DIALOG => {
+                    let text = cursor.read_str();
+                    self.pending_dialogs.push(text);
+                }
+                EV_JUMP_IF_FLAG => {
+                    let flag_id = cursor.read_u16_le();
+                    let target = cursor.read_u16_le();
+                    if *self.quest_flags.get(&flag_id).unwrap_or(&false) {
+                        cursor.seek(target as usize);
+                    }
+                }
+                EV_DELAY => {
+                    let cycles = cursor.read_u16_le();
+                    self.pending_timer = Some(cycles);
+                }
+                other => return EventExitReason::BadOpcode { offset, opcode: other },
+            }
+        }
+    }
+
+    /// Whether `entity` is within `DELTA_RELEVANCE_RADIUS` of the player,
+    /// and so worth spending bandwidth on in a delta snapshot.
+    fn is_relevant(&self, entity: &Entity) -> bool {
+        let dx = (entity.x as i32) - (self.player_x as i32);
+        let dy = (entity.y as i32) - (self.player_y as i32);
+        dx * dx + dy * dy <= DELTA_RELEVANCE_RADIUS * DELTA_RELEVANCE_RADIUS
+    }
+
+    /// Diff the live, relevance-filtered entity set against `baseline`
+    /// and encode the result as a sequence of per-entity entries: a new
+    /// entity is sent in full (as if diffed against a zero baseline), a
+    /// changed entity carries only its changed fields behind a bitmask,
+    /// an unchanged entity is omitted, and an entity the baseline still
+    /// has but that's no longer relevant (destroyed or out of range) is
+    /// sent as a removal marker.
+    pub fn write_delta(&self, baseline: &ClientBaseline) -> Vec<u8> {
+        let relevant: HashMap<u16, &Entity> = self
+            .entities
+            .values()
+            .filter(|e| self.is_relevant(e))
+            .map(|e| (e.id, e))
+            .collect();
+
+        let mut body = Vec::new();
+        let mut entry_count: u32 = 0;
+
+        for (&id, entity) in &relevant {
+            match baseline.entities.get(&id) {
+                None => {
+                    body.extend_from_slice(&id.to_le_bytes());
+                    body.push(DELTA_ENTRY_NEW);
+                    Self::write_entity_fields(&mut body, entity);
+                    entry_count += 1;
+                }
+                Some(prev) => {
+                    let mask = Self::changed_field_mask(prev, entity);
+                    if mask == 0 {
+                        continue;
+                    }
+                    body.extend_from_slice(&id.to_le_bytes());
+                    body.push(mask);
+                    Self::write_changed_fields(&mut body, prev, entity, mask);
+                    entry_count += 1;
+                }
+            }
+        }
+
+        for &id in baseline.entities.keys() {
+            if !relevant.contains_key(&id) {
+                body.extend_from_slice(&id.to_le_bytes());
+                body.push(DELTA_ENTRY_REMOVED);
+                entry_count += 1;
+            }
+        }
+
+        let mut out = Vec::with_capacity(4 + body.len());
+        out.extend_from_slice(&entry_count.to_le_bytes());
+        out.extend_from_slice(&body);
+        out
+    }
+
+    /// Apply a delta produced by `write_delta` to this world's entities,
+    /// reconstructing each changed entity from its current value (acting
+    /// as the baseline being updated) plus the changed fields carried in
+    /// the delta. Removal markers destroy the entity; new entries insert
+    /// it wholesale.
+    pub fn apply_delta(&mut self, data: &[u8]) -> Result<(), String> {
+        if data.len() < 4 {
+            return Err("truncated delta: missing entry count".to_string());
+        }
+        let count = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let mut pos = 4;
+
+        for _ in 0..count {
+            if pos + 3 > data.len() {
+                return Err("truncated delta entry header".to_string());
+            }
+            let id = u16::from_le_bytes([data[pos], data[pos + 1]]);
+            let flags = data[pos + 2];
+            pos += 3;
+
+            if flags & DELTA_ENTRY_REMOVED != 0 {
+                self.entities.remove(&id);
+                continue;
+            }
+
+            let mut entity = if flags & DELTA_ENTRY_NEW != 0 {
+                Entity { id, entity_type: 0, x: 0, y: 0, z: 0, properties: HashMap::new() }
+            } else {
+                self.entities
+                    .get(&id)
+                    .cloned()
+                    .ok_or_else(|| format!("delta updates unknown entity {}", id))?
+            };
+
+            // A NEW entry carries a full record (all 5 fields), mirroring
+            // `write_entity_fields`, not just the bits set in the flag byte.
+            let mask = if flags & DELTA_ENTRY_NEW != 0 { 0x1F } else { flags & 0x1F };
+            pos = Self::read_changed_fields(data, pos, &mut entity, mask)?;
+            self.entities.insert(id, entity);
+        }
+
+        Ok(())
+    }
+
+    /// Replace `client_id`'s baseline with the world's current
+    /// relevance-filtered entity set. The snapshot is taken when this is
+    /// called, not when the last delta was written: an entity changed in
+    /// between is recorded with its new state, which that client has not
+    /// received. Call once the client confirms receipt - never
+    /// unconditionally after `write_delta`, or a dropped packet would
+    /// desync the baseline from what the client actually has.
+    pub fn ack_baseline(&mut self, client_id: u16) {
+        let snapshot = self
+            .entities
+            .values()
+            .filter(|e| self.is_relevant(e))
+            .map(|e| (e.id, e.clone()))
+            .collect();
+        self.client_baselines.insert(client_id, ClientBaseline { entities: snapshot });
+    }
+
+    /// Convenience wrapper: diff against `client_id`'s tracked baseline
+    /// (an empty one if this client has never acknowledged a frame).
+    pub fn write_delta_for(&self, client_id: u16) -> Vec<u8> {
+        let empty = ClientBaseline::new();
+        let baseline = self.client_baselines.get(&client_id).unwrap_or(&empty);
+        self.write_delta(baseline)
+    }
+
+    fn changed_field_mask(prev: &Entity, current: &Entity) -> u8 {
+        let mut mask = 0u8;
+        if prev.entity_type != current.entity_type {
+            mask |= DELTA_FIELD_TYPE;
+        }
+        if prev.x != current.x {
+            mask |= DELTA_FIELD_X;
+        }
+        if prev.y != current.y {
+            mask |= DELTA_FIELD_Y;
+        }
+        if prev.z != current.z {
+            mask |= DELTA_FIELD_Z;
+        }
+        if prev.properties != current.properties {
+            mask |= DELTA_FIELD_PROPERTIES;
+        }
+        mask
+    }
+
+    fn write_entity_fields(out: &mut Vec<u8>, entity: &Entity) {
+        out.extend_from_slice(&entity.entity_type.to_le_bytes());
+        out.extend_from_slice(&entity.x.to_le_bytes());
+        out.extend_from_slice(&entity.y.to_le_bytes());
+        out.extend_from_slice(&entity.z.to_le_bytes());
+        Self::write_properties(out, &entity.properties);
+    }
+
+    fn write_changed_fields(out: &mut Vec<u8>, _prev: &Entity, current: &Entity, mask: u8) {
+        if mask & DELTA_FIELD_TYPE != 0 {
+            out.extend_from_slice(&current.entity_type.to_le_bytes());
+        }
+        if mask & DELTA_FIELD_X != 0 {
+            out.extend_from_slice(&current.x.to_le_bytes());
+        }
+        if mask & DELTA_FIELD_Y != 0 {
+            out.extend_from_slice(&current.y.to_le_bytes());
+        }
+        if mask & DELTA_FIELD_Z != 0 {
+            out.extend_from_slice(&current.z.to_le_bytes());
+        }
+        if mask & DELTA_FIELD_PROPERTIES != 0 {
+            Self::write_properties(out, &current.properties);
+        }
+    }
+
+    fn write_properties(out: &mut Vec<u8>, properties: &HashMap<String, u16>) {
+        out.extend_from_slice(&(properties.len() as u16).to_le_bytes());
+        for (key, value) in properties {
+            let key_bytes = key.as_bytes();
+            out.extend_from_slice(&(key_bytes.len() as u16).to_le_bytes());
+            out.extend_from_slice(key_bytes);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    fn read_changed_fields(
+        data: &[u8],
+        mut pos: usize,
+        entity: &mut Entity,
+        mask: u8,
+    ) -> Result<usize, String> {
+        if mask & DELTA_FIELD_TYPE != 0 {
+            if pos + 2 > data.len() {
+                return Err("truncated delta: entity_type field".to_string());
+            }
+            entity.entity_type = u16::from_le_bytes([data[pos], data[pos + 1]]);
+            pos += 2;
+        }
+        if mask & DELTA_FIELD_X != 0 {
+            if pos + 2 > data.len() {
+                return Err("truncated delta: x field".to_string());
+            }
+            entity.x = i16::from_le_bytes([data[pos], data[pos + 1]]);
+            pos += 2;
+        }
+        if mask & DELTA_FIELD_Y != 0 {
+            if pos + 2 > data.len() {
+                return Err("truncated delta: y field".to_string());
+            }
+            entity.y = i16::from_le_bytes([data[pos], data[pos + 1]]);
+            pos += 2;
+        }
+        if mask & DELTA_FIELD_Z != 0 {
+            if pos + 2 > data.len() {
+                return Err("truncated delta: z field".to_string());
+            }
+            entity.z = i16::from_le_bytes([data[pos], data[pos + 1]]);
+            pos += 2;
+        }
+        if mask & DELTA_FIELD_PROPERTIES != 0 {
+            if pos + 2 > data.len() {
+                return Err("truncated delta: property count".to_string());
+            }
+            let prop_count = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+            pos += 2;
+            let mut properties = HashMap::new();
+            for _ in 0..prop_count {
+                if pos + 2 > data.len() {
+                    return Err("truncated delta: property key length".to_string());
+                }
+                let key_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+                pos += 2;
+                if pos + key_len + 2 > data.len() {
+                    return Err("truncated delta: property".to_string());
+                }
+                let key = std::str::from_utf8(&data[pos..pos + key_len])
+                    .map_err(|_| "delta property key is not valid UTF-8".to_string())?
+                    .to_string();
+                pos += key_len;
+                let value = u16::from_le_bytes([data[pos], data[pos + 1]]);
+                pos += 2;
+                properties.insert(key, value);
+            }
+            entity.properties = properties;
+        }
+        Ok(pos)
+    }
+
+    /// Serialize this world into `out`: a header (magic + format
+    /// version) followed by its fields, tagged so `load_from` can skip
+    /// ones it doesn't recognize. Backs the `SaveGame` syscall.
+    pub fn save_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&SAVE_MAGIC_GAMEWORLD.to_le_bytes());
+        out.extend_from_slice(&SAVE_FORMAT_VERSION.to_le_bytes());
+
+        let mut w = FieldWriter::new(out);
+        w.write_i16(FIELD_PLAYER_X, self.player_x);
+        w.write_i16(FIELD_PLAYER_Y, self.player_y);
+        w.write_u16(FIELD_NEXT_ENTITY_ID, self.next_entity_id);
+        w.write_blob(FIELD_TILES_RLE, &rle_encode_tiles(&self.tiles));
+        w.write_blob(FIELD_QUEST_FLAGS, &encode_quest_flags(&self.quest_flags));
+        w.write_blob(FIELD_PLAYER_STATS, &encode_player_stats(&self.player_stats));
+        w.write_blob(FIELD_INVENTORY, &encode_inventory(&self.inventory));
+        w.write_blob(FIELD_ENTITIES, &encode_entities(&self.entities));
+    }
+
+    /// Reconstruct a world from bytes written by `save_to`. Validates
+    /// the magic tag and format version and rejects truncated data with
+    /// a descriptive error rather than panicking. `next_entity_id` is
+    /// bumped past the highest loaded entity id in case an older save
+    /// predates an entity that was since created. Backs the `LoadGame`
+    /// syscall.
+    pub fn load_from(data: &[u8]) -> Result<GameWorld, String> {
+        if data.len() < 6 {
+            return Err("truncated save data: missing header".to_string());
+        }
+        let magic = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        if magic != SAVE_MAGIC_GAMEWORLD {
+            return Err(format!("not a GameWorld save (bad magic 0x{:08X})", magic));
+        }
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        if version == 0 || version > SAVE_FORMAT_VERSION {
+            return Err(format!("unsupported GameWorld save version {}", version));
+        }
+
+        let mut world = GameWorld::new();
+        let mut max_entity_id = 0u16;
+        let mut reader = FieldReader::new(&data[6..]);
+        while let Some(field) = reader.next()? {
+            match field.id {
+                FIELD_PLAYER_X => {
+                    if let Some(v) = field.as_i16() {
+                        world.player_x = v;
+                    }
+                }
+                FIELD_PLAYER_Y => {
+                    if let Some(v) = field.as_i16() {
+                        world.player_y = v;
+                    }
+                }
+                FIELD_NEXT_ENTITY_ID => {
+                    if let Some(v) = field.as_u16() {
+                        world.next_entity_id = v;
+                    }
+                }
+                FIELD_TILES_RLE => {
+                    if let Some(blob) = field.as_blob() {
+                        world.tiles = rle_decode_tiles(blob)?;
+                    }
+                }
+                FIELD_QUEST_FLAGS => {
+                    if let Some(blob) = field.as_blob() {
+                        world.quest_flags = decode_quest_flags(blob)?;
+                    }
+                }
+                FIELD_PLAYER_STATS => {
+                    if let Some(blob) = field.as_blob() {
+                        world.player_stats = decode_player_stats(blob)?;
+                    }
+                }
+                FIELD_INVENTORY => {
+                    if let Some(blob) = field.as_blob() {
+                        world.inventory = decode_inventory(blob)?;
+                    }
+                }
+                FIELD_ENTITIES => {
+                    if let Some(blob) = field.as_blob() {
+                        world.entities = decode_entities(blob)?;
+                        max_entity_id = world.entities.keys().copied().max().unwrap_or(0);
+                    }
+                }
+                _ => {} // unknown field id - skip (forward/backward compatibility)
+            }
+        }
+
+        if world.next_entity_id <= max_entity_id {
+            world.next_entity_id = max_entity_id.wrapping_add(1);
+        }
+
+        Ok(world)
+    }
 }
 
 /// Reality engine state - THE DANGEROUS PART
@@ -395,6 +1199,44 @@ pub struct RealityEngine {
     pub reality_memory: Vec<u8>,
     pub modifications: Vec<RealityMod>,
     pub portals: Vec<Portal>,
+
+    /// Inverse of every mutating operation still retained, oldest first;
+    /// `revert` pops from the back and applies them. Ring-buffered at
+    /// `journal_cap` entries so long-running scripts can't grow it
+    /// unboundedly.
+    undo_stack: VecDeque<JournalOp>,
+    /// Inverses of operations most recently undone; `redo` pops from
+    /// here. Cleared whenever a new forward mutation is recorded, since
+    /// that mutation invalidates the redo history.
+    redo_stack: Vec<JournalOp>,
+    /// Named journal positions recorded by `checkpoint`, so a script can
+    /// `revert_to_checkpoint` a labeled save point instead of counting
+    /// steps by hand.
+    checkpoints: HashMap<String, usize>,
+    /// Journal position: incremented by each recorded forward mutation
+    /// and each `redo`, decremented by each step `revert` undoes;
+    /// `checkpoint` snapshots it.
+    journal_position: usize,
+    /// Maximum retained `undo_stack` length.
+    journal_cap: usize,
+}
+
+/// Default cap on the undo journal's length (see `RealityEngine::journal_cap`).
+const DEFAULT_JOURNAL_CAP: usize = 256;
+
+/// One entry in the undo/redo journal: whatever is needed to reverse a
+/// single mutating reality operation. `RealityEngine::apply_journal_op`
+/// applies an entry's effect and returns the entry that would reverse
+/// *that* application, so the same machinery drives both undo and redo.
+#[derive(Debug, Clone)]
+enum JournalOp {
+    RealityWrite { addr: u16, previous: u8 },
+    SetGravity { previous: f32 },
+    SetTimeFlow { previous: f32 },
+    RemovePortal { id: u16 },
+    RestorePortal { portal: Portal },
+    RemoveModification { id: u16 },
+    RestoreModification { modification: RealityMod },
 }
 
 #[derive(Debug, Clone)]
@@ -423,19 +1265,26 @@ impl RealityEngine {
             reality_memory: vec![0; 4096],  // 4KB reality buffer
             modifications: Vec::new(),
             portals: Vec::new(),
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            checkpoints: HashMap::new(),
+            journal_position: 0,
+            journal_cap: DEFAULT_JOURNAL_CAP,
         }
     }
-    
+
     pub fn reality_write(&mut self, addr: u16, value: u8) -> Result<(), String> {
         let addr = addr as usize;
         if addr < self.reality_memory.len() {
+            let previous = self.reality_memory[addr];
             self.reality_memory[addr] = value;
+            self.push_undo(JournalOp::RealityWrite { addr: addr as u16, previous });
             Ok(())
         } else {
             Err("Reality write out of bounds".to_string())
         }
     }
-    
+
     pub fn reality_read(&self, addr: u16) -> Result<u8, String> {
         let addr = addr as usize;
         if addr < self.reality_memory.len() {
@@ -444,53 +1293,944 @@ impl RealityEngine {
             Err("Reality read out of bounds".to_string())
         }
     }
-    
+
     pub fn set_gravity(&mut self, g: f32) {
+        let previous = self.gravity;
         self.gravity = g;
+        self.push_undo(JournalOp::SetGravity { previous });
     }
-    
+
     pub fn set_time_flow(&mut self, scale: f32) {
+        let previous = self.time_scale;
         self.time_scale = scale;
+        self.push_undo(JournalOp::SetTimeFlow { previous });
+    }
+
+    /// Create a new portal, failing if `id` is already in use. Records an
+    /// undo entry that removes the portal again.
+    pub fn create_portal(&mut self, id: u16, x1: i16, y1: i16, x2: i16, y2: i16) -> Result<(), String> {
+        if self.portals.iter().any(|p| p.id == id) {
+            return Err(format!("portal {} already exists", id));
+        }
+        self.portals.push(Portal { id, x1, y1, x2, y2 });
+        self.push_undo(JournalOp::RemovePortal { id });
+        Ok(())
+    }
+
+    /// Apply a reality modification, failing if its `id` is already in
+    /// use. Records an undo entry that removes the modification again.
+    pub fn apply_modification(&mut self, modification: RealityMod) -> Result<(), String> {
+        if self.modifications.iter().any(|m| m.id == modification.id) {
+            return Err(format!("modification {} already exists", modification.id));
+        }
+        let id = modification.id;
+        self.modifications.push(modification);
+        self.push_undo(JournalOp::RemoveModification { id });
+        Ok(())
+    }
+
+    /// Record a forward mutation's inverse on the undo stack, evicting
+    /// the oldest entry if `journal_cap` is exceeded, and drop the redo
+    /// history since it no longer follows from the new present.
+    fn push_undo(&mut self, op: JournalOp) {
+        if self.undo_stack.len() >= self.journal_cap {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(op);
+        self.redo_stack.clear();
+        self.journal_position += 1;
+    }
+
+    /// Apply `op`'s effect directly (bypassing `push_undo`, since this is
+    /// itself an undo/redo step rather than a new forward mutation) and
+    /// return the operation that would reverse this exact application.
+    /// Used symmetrically by both `revert` (applying entries popped from
+    /// `undo_stack`) and `redo` (applying entries popped from
+    /// `redo_stack`).
+    fn apply_journal_op(&mut self, op: JournalOp) -> Result<JournalOp, String> {
+        match op {
+            JournalOp::RealityWrite { addr, previous } => {
+                let idx = addr as usize;
+                if idx >= self.reality_memory.len() {
+                    return Err("journal entry addr out of bounds".to_string());
+                }
+                let current = self.reality_memory[idx];
+                self.reality_memory[idx] = previous;
+                Ok(JournalOp::RealityWrite { addr, previous: current })
+            }
+            JournalOp::SetGravity { previous } => {
+                let current = self.gravity;
+                self.gravity = previous;
+                Ok(JournalOp::SetGravity { previous: current })
+            }
+            JournalOp::SetTimeFlow { previous } => {
+                let current = self.time_scale;
+                self.time_scale = previous;
+                Ok(JournalOp::SetTimeFlow { previous: current })
+            }
+            JournalOp::RemovePortal { id } => {
+                let idx = self.portals.iter().position(|p| p.id == id)
+                    .ok_or_else(|| format!("journal entry refers to missing portal {}", id))?;
+                let portal = self.portals.remove(idx);
+                Ok(JournalOp::RestorePortal { portal })
+            }
+            JournalOp::RestorePortal { portal } => {
+                let id = portal.id;
+                self.portals.push(portal);
+                Ok(JournalOp::RemovePortal { id })
+            }
+            JournalOp::RemoveModification { id } => {
+                let idx = self.modifications.iter().position(|m| m.id == id)
+                    .ok_or_else(|| format!("journal entry refers to missing modification {}", id))?;
+                let modification = self.modifications.remove(idx);
+                Ok(JournalOp::RestoreModification { modification })
+            }
+            JournalOp::RestoreModification { modification } => {
+                let id = modification.id;
+                self.modifications.push(modification);
+                Ok(JournalOp::RemoveModification { id })
+            }
+        }
+    }
+
+    /// Undo up to `n` of the most recent mutations. Stops early if the
+    /// journal runs dry. If any step fails partway through, every step
+    /// already reverted in this call is re-applied in reverse (undoing
+    /// the undo) so the engine ends up exactly where it started, and the
+    /// whole batch is pushed back onto `undo_stack` unchanged; only then
+    /// is the error returned. On success, the reverted steps' inverses
+    /// are pushed onto `redo_stack` in the order they were undone, so the
+    /// last step undone is on top and `redo` replays it first, and the
+    /// number of steps actually reverted is returned.
+    pub fn revert(&mut self, n: usize) -> Result<usize, String> {
+        let mut done = Vec::new();
+        for _ in 0..n {
+            let op = match self.undo_stack.pop_back() {
+                Some(op) => op,
+                None => break,
+            };
+            match self.apply_journal_op(op.clone()) {
+                Ok(redo_op) => done.push((op, redo_op)),
+                Err(err) => {
+                    // Unwind: reapply what we already reverted, in
+                    // reverse, to get back to the pre-revert state, then
+                    // put every popped entry back in its original order.
+                    for (_, redo_op) in done.iter().rev() {
+                        let _ = self.apply_journal_op(redo_op.clone());
+                    }
+                    self.undo_stack.push_back(op);
+                    for (undone, _) in done.into_iter().rev() {
+                        self.undo_stack.push_back(undone);
+                    }
+                    return Err(format!("revert failed and was rolled back: {}", err));
+                }
+            }
+        }
+        let count = done.len();
+        self.journal_position = self.journal_position.saturating_sub(count);
+        for (_, redo_op) in done {
+            self.redo_stack.push(redo_op);
+        }
+        Ok(count)
+    }
+
+    /// Redo the most recently undone mutation, if any.
+    pub fn redo(&mut self) -> Result<(), String> {
+        let op = self.redo_stack.pop().ok_or_else(|| "nothing to redo".to_string())?;
+        match self.apply_journal_op(op.clone()) {
+            Ok(undo_op) => {
+                self.undo_stack.push_back(undo_op);
+                self.journal_position += 1;
+                Ok(())
+            }
+            Err(err) => {
+                self.redo_stack.push(op);
+                Err(err)
+            }
+        }
+    }
+
+    /// Record the current journal position under `name` for later
+    /// `revert_to_checkpoint`.
+    pub fn checkpoint(&mut self, name: impl Into<String>) {
+        self.checkpoints.insert(name.into(), self.journal_position);
+    }
+
+    /// Revert to a previously recorded checkpoint, returning the number
+    /// of steps actually reverted. Fails if the checkpoint is unknown, is
+    /// ahead of the current position, or is older than what the
+    /// ring-buffered `undo_stack` still retains.
+    pub fn revert_to_checkpoint(&mut self, name: &str) -> Result<usize, String> {
+        let target = *self.checkpoints.get(name)
+            .ok_or_else(|| format!("no checkpoint named '{}'", name))?;
+        if target > self.journal_position {
+            return Err(format!("checkpoint '{}' is ahead of the current journal position", name));
+        }
+        let steps = self.journal_position - target;
+        if steps > self.undo_stack.len() {
+            return Err(format!("checkpoint '{}' has fallen out of the journal window", name));
+        }
+        self.revert(steps)
+    }
+
+    /// Serialize this engine into `out`, same field-descriptor format as
+    /// `GameWorld::save_to`. Backs the `RealitySave` syscall.
+    pub fn save_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&SAVE_MAGIC_REALITY.to_le_bytes());
+        out.extend_from_slice(&SAVE_FORMAT_VERSION.to_le_bytes());
+
+        let mut w = FieldWriter::new(out);
+        w.write_f32(FIELD_GRAVITY, self.gravity);
+        w.write_f32(FIELD_TIME_SCALE, self.time_scale);
+        w.write_blob(FIELD_REALITY_MEMORY, &self.reality_memory);
+        w.write_blob(FIELD_MODIFICATIONS, &encode_modifications(&self.modifications));
+        w.write_blob(FIELD_PORTALS, &encode_portals(&self.portals));
+    }
+
+    /// Reconstruct an engine from bytes written by `save_to`, validating
+    /// the magic tag and version and rejecting truncated data with a
+    /// descriptive error. Backs the `RealityLoad` syscall.
+    pub fn load_from(data: &[u8]) -> Result<RealityEngine, String> {
+        if data.len() < 6 {
+            return Err("truncated save data: missing header".to_string());
+        }
+        let magic = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        if magic != SAVE_MAGIC_REALITY {
+            return Err(format!("not a RealityEngine save (bad magic 0x{:08X})", magic));
+        }
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        if version == 0 || version > SAVE_FORMAT_VERSION {
+            return Err(format!("unsupported RealityEngine save version {}", version));
+        }
+
+        let mut engine = RealityEngine::new();
+        let mut reader = FieldReader::new(&data[6..]);
+        while let Some(field) = reader.next()? {
+            match field.id {
+                FIELD_GRAVITY => {
+                    if let Some(v) = field.as_f32() {
+                        engine.gravity = v;
+                    }
+                }
+                FIELD_TIME_SCALE => {
+                    if let Some(v) = field.as_f32() {
+                        engine.time_scale = v;
+                    }
+                }
+                FIELD_REALITY_MEMORY => {
+                    if let Some(blob) = field.as_blob() {
+                        engine.reality_memory = blob.to_vec();
+                    }
+                }
+                FIELD_MODIFICATIONS => {
+                    if let Some(blob) = field.as_blob() {
+                        engine.modifications = decode_modifications(blob)?;
+                    }
+                }
+                FIELD_PORTALS => {
+                    if let Some(blob) = field.as_blob() {
+                        engine.portals = decode_portals(blob)?;
+                    }
+                }
+                _ => {} // unknown field id - skip (forward/backward compatibility)
+            }
+        }
+
+        Ok(engine)
+    }
+}
+
+// ============================================================================
+// Versioned save/load serialization
+//
+// Each savable struct (`GameWorld`, `RealityEngine`) writes a small
+// header (magic tag + format version) followed by a sequence of typed,
+// tagged fields. A field the loader doesn't recognize - from a newer
+// save written by a later version, or an older one missing a field this
+// version added - is skipped by its encoded length rather than treated
+// as an error, so saves stay forward- and backward-loadable.
+// ============================================================================
+
+const SAVE_FORMAT_VERSION: u16 = 1;
+const SAVE_MAGIC_GAMEWORLD: u32 = 0x47575356; // "GWSV"
+const SAVE_MAGIC_REALITY: u32 = 0x52454153;   // "REAS"
+
+/// Field ids for `GameWorld::save_to`/`load_from`.
+const FIELD_PLAYER_X: u16 = 1;
+const FIELD_PLAYER_Y: u16 = 2;
+const FIELD_NEXT_ENTITY_ID: u16 = 3;
+const FIELD_TILES_RLE: u16 = 4;
+const FIELD_QUEST_FLAGS: u16 = 5;
+const FIELD_PLAYER_STATS: u16 = 6;
+const FIELD_INVENTORY: u16 = 7;
+const FIELD_ENTITIES: u16 = 8;
+
+/// Field ids for `RealityEngine::save_to`/`load_from`.
+const FIELD_GRAVITY: u16 = 1;
+const FIELD_TIME_SCALE: u16 = 2;
+const FIELD_REALITY_MEMORY: u16 = 3;
+const FIELD_MODIFICATIONS: u16 = 4;
+const FIELD_PORTALS: u16 = 5;
+
+/// Type code stored alongside each field's id so a reader that doesn't
+/// recognize the id can still skip the right number of bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+enum FieldType {
+    U8 = 0,
+    U16 = 1,
+    I16 = 2,
+    F32 = 3,
+    Str = 4,
+    Blob = 5,
+}
+
+impl FieldType {
+    fn from_u8(v: u8) -> Result<Self, String> {
+        match v {
+            0 => Ok(FieldType::U8),
+            1 => Ok(FieldType::U16),
+            2 => Ok(FieldType::I16),
+            3 => Ok(FieldType::F32),
+            4 => Ok(FieldType::Str),
+            5 => Ok(FieldType::Blob),
+            other => Err(format!("unknown save field type code {}", other)),
+        }
+    }
+}
+
+/// Appends `(field_id, type_code, payload)` triples to a byte buffer.
+/// Fixed-width types carry no length prefix; `Str`/`Blob` are
+/// length-prefixed (u32) so a reader can skip over an id it doesn't
+/// recognize.
+struct FieldWriter<'a> {
+    buf: &'a mut Vec<u8>,
+}
+
+impl<'a> FieldWriter<'a> {
+    fn new(buf: &'a mut Vec<u8>) -> Self {
+        FieldWriter { buf }
+    }
+
+    fn tag(&mut self, id: u16, ty: FieldType) {
+        self.buf.extend_from_slice(&id.to_le_bytes());
+        self.buf.push(ty as u8);
+    }
+
+    fn write_i16(&mut self, id: u16, value: i16) {
+        self.tag(id, FieldType::I16);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u16(&mut self, id: u16, value: u16) {
+        self.tag(id, FieldType::U16);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_f32(&mut self, id: u16, value: f32) {
+        self.tag(id, FieldType::F32);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_blob(&mut self, id: u16, value: &[u8]) {
+        self.tag(id, FieldType::Blob);
+        self.buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        self.buf.extend_from_slice(value);
+    }
+}
+
+/// One decoded field: its id, type, and raw (still type-encoded) payload.
+struct Field<'a> {
+    id: u16,
+    ty: FieldType,
+    payload: &'a [u8],
+}
+
+impl<'a> Field<'a> {
+    fn as_i16(&self) -> Option<i16> {
+        (self.ty == FieldType::I16 && self.payload.len() == 2)
+            .then(|| i16::from_le_bytes([self.payload[0], self.payload[1]]))
+    }
+
+    fn as_u16(&self) -> Option<u16> {
+        (self.ty == FieldType::U16 && self.payload.len() == 2)
+            .then(|| u16::from_le_bytes([self.payload[0], self.payload[1]]))
+    }
+
+    fn as_f32(&self) -> Option<f32> {
+        (self.ty == FieldType::F32 && self.payload.len() == 4).then(|| {
+            f32::from_le_bytes([self.payload[0], self.payload[1], self.payload[2], self.payload[3]])
+        })
+    }
+
+    fn as_blob(&self) -> Option<&'a [u8]> {
+        (self.ty == FieldType::Blob).then_some(self.payload)
+    }
+}
+
+/// Reads back the `(field_id, type_code, payload)` triples written by
+/// `FieldWriter`. Returns `Ok(None)` at a clean end of buffer and `Err`
+/// on a header or length prefix that runs past the end of the data -
+/// never panics on truncated input.
+struct FieldReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> FieldReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        FieldReader { data, pos: 0 }
+    }
+
+    fn next(&mut self) -> Result<Option<Field<'a>>, String> {
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+        if self.pos + 3 > self.data.len() {
+            return Err("truncated save data: incomplete field header".to_string());
+        }
+
+        let id = u16::from_le_bytes([self.data[self.pos], self.data[self.pos + 1]]);
+        let ty = FieldType::from_u8(self.data[self.pos + 2])?;
+        self.pos += 3;
+
+        let len = match ty {
+            FieldType::U8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::F32 => 4,
+            FieldType::Str | FieldType::Blob => {
+                if self.pos + 4 > self.data.len() {
+                    return Err("truncated save data: incomplete field length prefix".to_string());
+                }
+                let len = u32::from_le_bytes([
+                    self.data[self.pos],
+                    self.data[self.pos + 1],
+                    self.data[self.pos + 2],
+                    self.data[self.pos + 3],
+                ]) as usize;
+                self.pos += 4;
+                len
+            }
+        };
+
+        if self.pos + len > self.data.len() {
+            return Err(format!("truncated save data: field {} payload runs past end of buffer", id));
+        }
+        let payload = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(Some(Field { id, ty, payload }))
+    }
+}
+
+/// Run-length encode the 256x256 tile grid: most tiles are 0, so storing
+/// `(value: u16, run_length: u16)` pairs is far smaller than the raw
+/// 128KB grid.
+fn rle_encode_tiles(tiles: &[Vec<u16>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut iter = tiles.iter().flatten().copied();
+    let mut current = match iter.next() {
+        Some(v) => v,
+        None => return out,
+    };
+    let mut run: u32 = 1;
+
+    for value in iter {
+        if value == current && run < u16::MAX as u32 {
+            run += 1;
+        } else {
+            out.extend_from_slice(&current.to_le_bytes());
+            out.extend_from_slice(&(run as u16).to_le_bytes());
+            current = value;
+            run = 1;
+        }
+    }
+    out.extend_from_slice(&current.to_le_bytes());
+    out.extend_from_slice(&(run as u16).to_le_bytes());
+    out
+}
+
+fn rle_decode_tiles(data: &[u8]) -> Result<Vec<Vec<u16>>, String> {
+    const CELLS: usize = 256 * 256;
+    let mut flat = Vec::with_capacity(CELLS);
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        let value = u16::from_le_bytes([data[pos], data[pos + 1]]);
+        let run = u16::from_le_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        flat.extend(std::iter::repeat(value).take(run));
+        pos += 4;
+    }
+    if flat.len() != CELLS {
+        return Err(format!("tile grid RLE decoded to {} cells, expected {}", flat.len(), CELLS));
+    }
+    Ok(flat.chunks(256).map(|row| row.to_vec()).collect())
+}
+
+fn encode_quest_flags(flags: &HashMap<u16, bool>) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(flags.len() as u32).to_le_bytes());
+    for (&id, &value) in flags {
+        out.extend_from_slice(&id.to_le_bytes());
+        out.push(value as u8);
+    }
+    out
+}
+
+fn decode_quest_flags(data: &[u8]) -> Result<HashMap<u16, bool>, String> {
+    if data.len() < 4 {
+        return Err("truncated quest flag table".to_string());
+    }
+    let count = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let mut pos = 4;
+    let mut map = HashMap::new();
+    for _ in 0..count {
+        if pos + 3 > data.len() {
+            return Err("truncated quest flag entry".to_string());
+        }
+        let id = u16::from_le_bytes([data[pos], data[pos + 1]]);
+        map.insert(id, data[pos + 2] != 0);
+        pos += 3;
+    }
+    Ok(map)
+}
+
+fn encode_player_stats(stats: &HashMap<u16, u16>) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(stats.len() as u32).to_le_bytes());
+    for (&id, &value) in stats {
+        out.extend_from_slice(&id.to_le_bytes());
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out
+}
+
+fn decode_player_stats(data: &[u8]) -> Result<HashMap<u16, u16>, String> {
+    if data.len() < 4 {
+        return Err("truncated player stats table".to_string());
+    }
+    let count = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let mut pos = 4;
+    let mut map = HashMap::new();
+    for _ in 0..count {
+        if pos + 4 > data.len() {
+            return Err("truncated player stats entry".to_string());
+        }
+        let id = u16::from_le_bytes([data[pos], data[pos + 1]]);
+        let value = u16::from_le_bytes([data[pos + 2], data[pos + 3]]);
+        map.insert(id, value);
+        pos += 4;
+    }
+    Ok(map)
+}
+
+fn encode_inventory(items: &[u16]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(items.len() as u32).to_le_bytes());
+    for &item in items {
+        out.extend_from_slice(&item.to_le_bytes());
     }
+    out
 }
 
-/// Memory allocator for user space
+fn decode_inventory(data: &[u8]) -> Result<Vec<u16>, String> {
+    if data.len() < 4 {
+        return Err("truncated inventory list".to_string());
+    }
+    let count = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let mut pos = 4;
+    let mut items = Vec::with_capacity(count);
+    for _ in 0..count {
+        if pos + 2 > data.len() {
+            return Err("truncated inventory entry".to_string());
+        }
+        items.push(u16::from_le_bytes([data[pos], data[pos + 1]]));
+        pos += 2;
+    }
+    Ok(items)
+}
+
+fn encode_entities(entities: &HashMap<u16, Entity>) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(entities.len() as u32).to_le_bytes());
+    for entity in entities.values() {
+        out.extend_from_slice(&entity.id.to_le_bytes());
+        out.extend_from_slice(&entity.entity_type.to_le_bytes());
+        out.extend_from_slice(&entity.x.to_le_bytes());
+        out.extend_from_slice(&entity.y.to_le_bytes());
+        out.extend_from_slice(&entity.z.to_le_bytes());
+        out.extend_from_slice(&(entity.properties.len() as u16).to_le_bytes());
+        for (key, value) in &entity.properties {
+            let key_bytes = key.as_bytes();
+            out.extend_from_slice(&(key_bytes.len() as u16).to_le_bytes());
+            out.extend_from_slice(key_bytes);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    out
+}
+
+fn decode_entities(data: &[u8]) -> Result<HashMap<u16, Entity>, String> {
+    if data.len() < 4 {
+        return Err("truncated entity table".to_string());
+    }
+    let count = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let mut pos = 4;
+    let mut entities = HashMap::new();
+
+    for _ in 0..count {
+        if pos + 10 > data.len() {
+            return Err("truncated entity record".to_string());
+        }
+        let id = u16::from_le_bytes([data[pos], data[pos + 1]]);
+        let entity_type = u16::from_le_bytes([data[pos + 2], data[pos + 3]]);
+        let x = i16::from_le_bytes([data[pos + 4], data[pos + 5]]);
+        let y = i16::from_le_bytes([data[pos + 6], data[pos + 7]]);
+        let z = i16::from_le_bytes([data[pos + 8], data[pos + 9]]);
+        pos += 10;
+
+        if pos + 2 > data.len() {
+            return Err("truncated entity property count".to_string());
+        }
+        let prop_count = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+        pos += 2;
+
+        let mut properties = HashMap::new();
+        for _ in 0..prop_count {
+            if pos + 2 > data.len() {
+                return Err("truncated entity property key length".to_string());
+            }
+            let key_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+            pos += 2;
+            if pos + key_len + 2 > data.len() {
+                return Err("truncated entity property".to_string());
+            }
+            let key = std::str::from_utf8(&data[pos..pos + key_len])
+                .map_err(|_| "entity property key is not valid UTF-8".to_string())?
+                .to_string();
+            pos += key_len;
+            let value = u16::from_le_bytes([data[pos], data[pos + 1]]);
+            pos += 2;
+            properties.insert(key, value);
+        }
+
+        entities.insert(id, Entity { id, entity_type, x, y, z, properties });
+    }
+
+    Ok(entities)
+}
+
+/// Encode `KvStore`'s tables for `open_file`/`flush`: a table count,
+/// then per table its name and an entry count, then per entry its key
+/// and `u32` value - the same length-prefixed shape as `encode_entities`.
+fn encode_kv_tables(tables: &HashMap<String, BTreeMap<String, u32>>) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+    for (name, table) in tables {
+        let name_bytes = name.as_bytes();
+        out.extend_from_slice(&(name_bytes.len() as u16).to_le_bytes());
+        out.extend_from_slice(name_bytes);
+        out.extend_from_slice(&(table.len() as u32).to_le_bytes());
+        for (key, value) in table {
+            let key_bytes = key.as_bytes();
+            out.extend_from_slice(&(key_bytes.len() as u16).to_le_bytes());
+            out.extend_from_slice(key_bytes);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    out
+}
+
+fn decode_kv_tables(data: &[u8]) -> Result<HashMap<String, BTreeMap<String, u32>>, String> {
+    if data.len() < 4 {
+        return Err("truncated kv store: missing table count".to_string());
+    }
+    let table_count = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let mut pos = 4;
+    let mut tables = HashMap::new();
+
+    for _ in 0..table_count {
+        if pos + 2 > data.len() {
+            return Err("truncated kv store: table name length".to_string());
+        }
+        let name_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+        pos += 2;
+        if pos + name_len + 4 > data.len() {
+            return Err("truncated kv store: table name".to_string());
+        }
+        let name = std::str::from_utf8(&data[pos..pos + name_len])
+            .map_err(|_| "kv store table name is not valid UTF-8".to_string())?
+            .to_string();
+        pos += name_len;
+
+        let entry_count = u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        pos += 4;
+
+        let mut table = BTreeMap::new();
+        for _ in 0..entry_count {
+            if pos + 2 > data.len() {
+                return Err("truncated kv store: key length".to_string());
+            }
+            let key_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+            pos += 2;
+            if pos + key_len + 4 > data.len() {
+                return Err("truncated kv store: entry".to_string());
+            }
+            let key = std::str::from_utf8(&data[pos..pos + key_len])
+                .map_err(|_| "kv store key is not valid UTF-8".to_string())?
+                .to_string();
+            pos += key_len;
+            let value = u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+            pos += 4;
+            table.insert(key, value);
+        }
+
+        tables.insert(name, table);
+    }
+
+    Ok(tables)
+}
+
+fn encode_modifications(mods: &[RealityMod]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(mods.len() as u32).to_le_bytes());
+    for m in mods {
+        out.extend_from_slice(&m.id.to_le_bytes());
+        out.extend_from_slice(&m.mod_type.to_le_bytes());
+        out.extend_from_slice(&m.target.to_le_bytes());
+        out.extend_from_slice(&m.value.to_le_bytes());
+        out.push(m.active as u8);
+    }
+    out
+}
+
+fn decode_modifications(data: &[u8]) -> Result<Vec<RealityMod>, String> {
+    if data.len() < 4 {
+        return Err("truncated modification list".to_string());
+    }
+    let count = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let mut pos = 4;
+    let mut mods = Vec::with_capacity(count);
+    for _ in 0..count {
+        if pos + 9 > data.len() {
+            return Err("truncated modification entry".to_string());
+        }
+        mods.push(RealityMod {
+            id: u16::from_le_bytes([data[pos], data[pos + 1]]),
+            mod_type: u16::from_le_bytes([data[pos + 2], data[pos + 3]]),
+            target: u16::from_le_bytes([data[pos + 4], data[pos + 5]]),
+            value: u16::from_le_bytes([data[pos + 6], data[pos + 7]]),
+            active: data[pos + 8] != 0,
+        });
+        pos += 9;
+    }
+    Ok(mods)
+}
+
+fn encode_portals(portals: &[Portal]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(portals.len() as u32).to_le_bytes());
+    for p in portals {
+        out.extend_from_slice(&p.id.to_le_bytes());
+        out.extend_from_slice(&p.x1.to_le_bytes());
+        out.extend_from_slice(&p.y1.to_le_bytes());
+        out.extend_from_slice(&p.x2.to_le_bytes());
+        out.extend_from_slice(&p.y2.to_le_bytes());
+    }
+    out
+}
+
+fn decode_portals(data: &[u8]) -> Result<Vec<Portal>, String> {
+    if data.len() < 4 {
+        return Err("truncated portal list".to_string());
+    }
+    let count = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let mut pos = 4;
+    let mut portals = Vec::with_capacity(count);
+    for _ in 0..count {
+        if pos + 10 > data.len() {
+            return Err("truncated portal entry".to_string());
+        }
+        portals.push(Portal {
+            id: u16::from_le_bytes([data[pos], data[pos + 1]]),
+            x1: i16::from_le_bytes([data[pos + 2], data[pos + 3]]),
+            y1: i16::from_le_bytes([data[pos + 4], data[pos + 5]]),
+            x2: i16::from_le_bytes([data[pos + 6], data[pos + 7]]),
+            y2: i16::from_le_bytes([data[pos + 8], data[pos + 9]]),
+        });
+        pos += 10;
+    }
+    Ok(portals)
+}
+
+/// Memory allocator for user space: a first-fit free-list allocator.
+/// `free_blocks` tracks every free region, sorted by address, so `alloc`
+/// can scan for the first block that fits and `free` can coalesce a
+/// returned block with whichever free neighbors are immediately adjacent
+/// to it, fighting the fragmentation a pure bump allocator can't recover
+/// from.
 pub struct MemoryAllocator {
     heap_start: u16,
     heap_end: u16,
-    allocations: HashMap<u16, u16>, // address -> size
-    next_addr: u16,
+    allocations: HashMap<u16, u16>,   // address -> size, for live allocations
+    free_blocks: BTreeMap<u16, u16>,  // address -> size, ordered for coalescing
 }
 
 impl MemoryAllocator {
     pub fn new(heap_start: u16, heap_end: u16) -> Self {
+        let mut free_blocks = BTreeMap::new();
+        free_blocks.insert(heap_start, heap_end - heap_start);
         MemoryAllocator {
             heap_start,
             heap_end,
             allocations: HashMap::new(),
-            next_addr: heap_start,
+            free_blocks,
         }
     }
-    
+
+    /// Find the first free block (lowest address) large enough for
+    /// `size`, split off the leftover space as a new free block if any
+    /// remains, and hand back the allocation's address.
     pub fn alloc(&mut self, size: u16) -> Option<u16> {
-        if self.next_addr + size > self.heap_end {
+        if size == 0 {
             return None;
         }
-        
-        let addr = self.next_addr;
+        let addr = *self
+            .free_blocks
+            .iter()
+            .find(|(_, &block_size)| block_size >= size)?
+            .0;
+        let block_size = self.free_blocks.remove(&addr).unwrap();
+        if block_size > size {
+            self.free_blocks.insert(addr + size, block_size - size);
+        }
         self.allocations.insert(addr, size);
-        self.next_addr += size;
         Some(addr)
     }
-    
+
+    /// Return the allocation at `addr` to the free list, coalescing it
+    /// with the free block immediately below (ending where this one
+    /// starts) and/or immediately above (starting where this one ends),
+    /// so adjacent frees merge back into one contiguous block instead of
+    /// leaking fragmentation.
     pub fn free(&mut self, addr: u16) -> bool {
-        self.allocations.remove(&addr).is_some()
+        let size = match self.allocations.remove(&addr) {
+            Some(size) => size,
+            None => return false,
+        };
+
+        let mut new_addr = addr;
+        let mut new_size = size;
+
+        if let Some((&below_addr, &below_size)) = self.free_blocks.range(..addr).next_back() {
+            if below_addr + below_size == addr {
+                self.free_blocks.remove(&below_addr);
+                new_addr = below_addr;
+                new_size += below_size;
+            }
+        }
+
+        let above_addr = new_addr + new_size;
+        if let Some(&above_size) = self.free_blocks.get(&above_addr) {
+            self.free_blocks.remove(&above_addr);
+            new_size += above_size;
+        }
+
+        self.free_blocks.insert(new_addr, new_size);
+        true
     }
-    
+
+    /// Used bytes, free bytes (including space lost to fragmentation
+    /// across multiple free blocks), and total heap capacity.
     pub fn get_stats(&self) -> (u16, u16, u16) {
-        let used = self.next_addr - self.heap_start;
         let total = self.heap_end - self.heap_start;
+        let used: u16 = self.allocations.values().copied().sum();
         let free = total - used;
         (used, free, total)
     }
+
+    /// Size of the largest single contiguous free block, so `AllocMem`
+    /// can fail gracefully on a request fragmentation can't satisfy
+    /// rather than blindly trying and getting a generic `None`.
+    pub fn largest_free_block(&self) -> u16 {
+        self.free_blocks.values().copied().max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod delta_tests {
+    use super::*;
+
+    #[test]
+    fn write_delta_against_empty_baseline_round_trips_new_entities() {
+        let mut world = GameWorld::new();
+        let id = world.create_entity(7, 130, 126);
+        world.entities.get_mut(&id).unwrap().z = 3;
+        world
+            .entities
+            .get_mut(&id)
+            .unwrap()
+            .properties
+            .insert("hp".to_string(), 42);
+
+        let delta = world.write_delta(&ClientBaseline::new());
+
+        let mut fresh = GameWorld::new();
+        fresh.apply_delta(&delta).unwrap();
+
+        let entity = fresh.entities.get(&id).expect("entity should be inserted");
+        assert_eq!(entity.entity_type, 7);
+        assert_eq!(entity.x, 130);
+        assert_eq!(entity.y, 126);
+        assert_eq!(entity.z, 3);
+        assert_eq!(entity.properties.get("hp"), Some(&42));
+    }
+}
+
+#[cfg(test)]
+mod kv_store_tests {
+    use super::*;
+
+    /// A path under the OS temp dir unique enough not to collide with a
+    /// parallel test run; removed on drop so a failed assertion doesn't
+    /// leave a stale file behind for the next run to trip over.
+    struct TempDbFile(PathBuf);
+
+    impl TempDbFile {
+        fn new(name: &str) -> Self {
+            let mut path = std::env::temp_dir();
+            path.push(format!("cvere_kv_store_test_{}_{}.db", std::process::id(), name));
+            let _ = fs::remove_file(&path);
+            TempDbFile(path)
+        }
+    }
+
+    impl Drop for TempDbFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn put_survives_reopening_the_store() {
+        let file = TempDbFile::new("put_survives");
+
+        let mut store = KvStore::open_file(&file.0).unwrap();
+        assert_eq!(store.open("high_scores"), DB_OK);
+        assert_eq!(store.put("alice", 9001), DB_OK);
+
+        // Simulate the process exiting and restarting: a fresh `KvStore`
+        // reloading the same file should see what was written, without
+        // any explicit close/save step.
+        let mut reopened = KvStore::open_file(&file.0).unwrap();
+        assert_eq!(reopened.open("high_scores"), DB_OK);
+        assert_eq!(reopened.get("alice"), (DB_OK, 9001));
+    }
+
+    #[test]
+    fn open_file_on_missing_path_starts_empty() {
+        let file = TempDbFile::new("missing");
+
+        let mut store = KvStore::open_file(&file.0).unwrap();
+        assert_eq!(store.open("table"), DB_OK);
+        assert_eq!(store.get("nope"), (DB_NOT_FOUND, 0));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,380 @@
+// ============================================================================
+// desktop/rust/src/assembler.rs
+// Two-pass assembler for the CVERE instruction set
+// ============================================================================
+//
+// Mirrors the opcode layout `InstructionDecoder` expects:
+//   R-Type:  [op:4][rd:4][rs:4][rt:4]         ADD SUB AND OR XOR NOT SHL SHR
+//   I-Type:  [op:4][rd:4][imm8:8]             ADDI LOADI
+//   M-Type:  [op:4][rd:4][rs:4][offset:4]     LOAD STORE
+//   J-Type:  [op:4][addr12:12]                JMP
+//   B-Type:  [op:4][rd:4][imm8:8]             BEQ BNE
+//   Extended:[op:4][sub:4][..][second word]   CALL RET PUSH POP RTI
+//   Special: full-word literal                NOP HALT
+
+use crate::diagnostics::{Diagnostic, DiagnosticBag, Span};
+use std::collections::HashMap;
+
+/// A fully assembled program, ready to be loaded into `Memory`.
+pub struct AssembledProgram {
+    /// Address the first word should be loaded at.
+    pub origin: u16,
+    /// Words in load order, starting at `origin`.
+    pub words: Vec<u16>,
+}
+
+const R_TYPE: &[(&str, u16)] = &[
+    ("ADD", 0x1), ("SUB", 0x3), ("AND", 0x4), ("OR", 0x5),
+    ("XOR", 0x6), ("NOT", 0x7), ("SHL", 0x8), ("SHR", 0x9),
+];
+const I_TYPE: &[(&str, u16)] = &[("ADDI", 0x2), ("LOADI", 0xC)];
+const M_TYPE: &[(&str, u16)] = &[("LOAD", 0xA), ("STORE", 0xB)];
+const B_TYPE: &[(&str, u16)] = &[("BEQ", 0xE), ("BNE", 0xF)];
+const J_TYPE_OP: u16 = 0xD;
+const EXTENDED_OP: u16 = 0x0;
+const EXT_SUB: &[(&str, u16)] = &[("CALL", 0x1), ("RET", 0x2), ("PUSH", 0x3), ("POP", 0x4), ("RTI", 0x5)];
+const NOP_WORD: u16 = 0x0000;
+const HALT_WORD: u16 = 0xFFFF;
+
+struct SourceLine<'a> {
+    number: usize,
+    text: &'a str,
+}
+
+enum Item {
+    Instruction { mnemonic: String, operands: Vec<Operand>, words: u16, line: usize, line_text: String },
+    Org(u16),
+    RawWord(u16),
+    Ascii(String),
+}
+
+#[derive(Clone)]
+enum Operand {
+    Reg(u8),
+    Imm(i32),
+    Label(String),
+}
+
+/// Assemble CVERE source text into a loadable program. On failure, returns
+/// every diagnostic collected across the pass rather than just the first.
+pub fn assemble(source: &str) -> Result<AssembledProgram, Vec<Diagnostic>> {
+    let lines: Vec<SourceLine> = source
+        .lines()
+        .enumerate()
+        .map(|(i, text)| SourceLine { number: i + 1, text })
+        .collect();
+
+    let mut errors = DiagnosticBag::new();
+    let mut items = Vec::new();
+
+    // ---- Pass 1: parse lines into items, tracking addresses and labels ----
+    let mut address: u16 = 0;
+    let mut origin: u16 = 0;
+    let mut origin_set = false;
+    let mut labels: HashMap<String, u16> = HashMap::new();
+
+    for line in &lines {
+        let text = strip_comment(line.text).trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        let mut rest = text;
+        while let Some(colon) = rest.find(':') {
+            let label = rest[..colon].trim().to_string();
+            if label.is_empty() || label.contains(char::is_whitespace) {
+                break;
+            }
+            labels.insert(label, address);
+            rest = rest[colon + 1..].trim();
+        }
+        if rest.is_empty() {
+            continue;
+        }
+
+        match parse_directive_or_instruction(rest, line.number, line.text) {
+            Ok(Item::Org(addr)) => {
+                if !origin_set {
+                    origin = addr;
+                    origin_set = true;
+                }
+                address = addr;
+                items.push(Item::Org(addr));
+            }
+            Ok(Item::RawWord(w)) => {
+                items.push(Item::RawWord(w));
+                address = address.wrapping_add(2);
+            }
+            Ok(Item::Ascii(s)) => {
+                let word_count = (s.len() as u16 + 1) / 2;
+                address = address.wrapping_add(word_count * 2);
+                items.push(Item::Ascii(s));
+            }
+            Ok(Item::Instruction { mnemonic, operands, words, line: line_no, line_text }) => {
+                address = address.wrapping_add(words * 2);
+                items.push(Item::Instruction { mnemonic, operands, words, line: line_no, line_text });
+            }
+            Err(diag) => errors.push(diag),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.into_vec());
+    }
+
+    // ---- Pass 2: emit words, resolving label references ----
+    let mut words = Vec::new();
+    let mut address: u16 = origin;
+
+    for item in items {
+        match item {
+            Item::Org(addr) => {
+                let gap_words = addr.wrapping_sub(address) / 2;
+                for _ in 0..gap_words {
+                    words.push(NOP_WORD);
+                }
+                address = addr;
+            }
+            Item::RawWord(w) => {
+                words.push(w);
+                address = address.wrapping_add(2);
+            }
+            Item::Ascii(s) => {
+                let bytes = s.as_bytes();
+                let mut i = 0;
+                while i < bytes.len() {
+                    let lo = bytes[i];
+                    let hi = if i + 1 < bytes.len() { bytes[i + 1] } else { 0 };
+                    words.push((lo as u16) | ((hi as u16) << 8));
+                    i += 2;
+                }
+                address = address.wrapping_add(((bytes.len() as u16) + 1) / 2 * 2);
+            }
+            Item::Instruction { mnemonic, operands, words: word_count, line, line_text } => {
+                let next_pc = address.wrapping_add(word_count * 2);
+                match encode(&mnemonic, &operands, next_pc, &labels) {
+                    Ok(encoded) => words.extend(encoded),
+                    Err(msg) => errors.push(Diagnostic::new(Span::whole_line(line, &line_text), msg, line_text.clone())),
+                }
+                address = next_pc;
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.into_vec());
+    }
+
+    Ok(AssembledProgram { origin, words })
+}
+
+fn strip_comment(line: &str) -> &str {
+    for (i, c) in line.char_indices() {
+        if c == ';' || c == '#' {
+            return &line[..i];
+        }
+    }
+    line
+}
+
+fn parse_directive_or_instruction(text: &str, line_no: usize, raw_line: &str) -> Result<Item, Diagnostic> {
+    let mut parts = text.splitn(2, char::is_whitespace);
+    let head = parts.next().unwrap_or("");
+    let tail = parts.next().unwrap_or("").trim();
+
+    if head.eq_ignore_ascii_case(".org") {
+        let addr = parse_imm(tail)
+            .ok_or_else(|| diag(line_no, raw_line, tail, format!("invalid .org address '{}'", tail)))?;
+        return Ok(Item::Org(addr as u16));
+    }
+    if head.eq_ignore_ascii_case(".word") {
+        let value = parse_imm(tail)
+            .ok_or_else(|| diag(line_no, raw_line, tail, format!("invalid .word value '{}'", tail)))?;
+        return Ok(Item::RawWord(value as u16));
+    }
+    if head.eq_ignore_ascii_case(".ascii") {
+        let s = parse_string_literal(tail)
+            .ok_or_else(|| diag(line_no, raw_line, tail, "expected a quoted string".to_string()))?;
+        return Ok(Item::Ascii(s));
+    }
+
+    let mnemonic = head.to_uppercase();
+    let operands: Vec<Operand> = if tail.is_empty() {
+        Vec::new()
+    } else {
+        let mut ops = Vec::new();
+        for raw in tail.split(',') {
+            ops.push(parse_operand(raw.trim(), line_no, raw_line)?);
+        }
+        ops
+    };
+
+    if !is_known_mnemonic(&mnemonic) {
+        return Err(diag(line_no, raw_line, head, format!("unknown mnemonic '{}'", mnemonic)));
+    }
+
+    let words = match mnemonic.as_str() {
+        "CALL" | "PUSH" | "POP" => 2,
+        _ => 1,
+    };
+
+    Ok(Item::Instruction { mnemonic, operands, words, line: line_no, line_text: raw_line.to_string() })
+}
+
+fn is_known_mnemonic(m: &str) -> bool {
+    R_TYPE.iter().any(|(n, _)| *n == m)
+        || I_TYPE.iter().any(|(n, _)| *n == m)
+        || M_TYPE.iter().any(|(n, _)| *n == m)
+        || B_TYPE.iter().any(|(n, _)| *n == m)
+        || EXT_SUB.iter().any(|(n, _)| *n == m)
+        || matches!(m, "JMP" | "NOP" | "HALT")
+}
+
+fn parse_operand(raw: &str, line_no: usize, raw_line: &str) -> Result<Operand, Diagnostic> {
+    if let Some(reg) = parse_register(raw) {
+        return Ok(Operand::Reg(reg));
+    }
+    if let Some(imm) = parse_imm(raw) {
+        return Ok(Operand::Imm(imm));
+    }
+    if raw.chars().next().map(|c| c.is_alphabetic() || c == '_').unwrap_or(false) {
+        return Ok(Operand::Label(raw.to_string()));
+    }
+    Err(diag(line_no, raw_line, raw, format!("invalid operand '{}'", raw)))
+}
+
+fn parse_register(raw: &str) -> Option<u8> {
+    let raw = raw.trim();
+    if !raw.to_uppercase().starts_with('R') {
+        return None;
+    }
+    u8::from_str_radix(&raw[1..], 16).ok().filter(|&r| r < 16)
+}
+
+fn parse_imm(raw: &str) -> Option<i32> {
+    let raw = raw.trim();
+    let (neg, raw) = if let Some(stripped) = raw.strip_prefix('-') { (true, stripped) } else { (false, raw) };
+    let value = if let Some(hex) = raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")) {
+        i32::from_str_radix(hex, 16).ok()?
+    } else {
+        raw.parse::<i32>().ok()?
+    };
+    Some(if neg { -value } else { value })
+}
+
+fn parse_string_literal(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+        Some(raw[1..raw.len() - 1].to_string())
+    } else {
+        None
+    }
+}
+
+fn diag(line: usize, raw_line: &str, token: &str, message: String) -> Diagnostic {
+    Diagnostic::new(Span::locate(line, raw_line, token), message, raw_line.to_string())
+}
+
+fn resolve(op: &Operand, labels: &HashMap<String, u16>) -> Result<i32, String> {
+    match op {
+        Operand::Reg(_) => Err("expected immediate, found register".to_string()),
+        Operand::Imm(v) => Ok(*v),
+        Operand::Label(name) => labels
+            .get(name)
+            .map(|&addr| addr as i32)
+            .ok_or_else(|| format!("undefined label '{}'", name)),
+    }
+}
+
+fn reg(op: &Operand) -> Result<u8, String> {
+    match op {
+        Operand::Reg(r) => Ok(*r),
+        _ => Err("expected register operand".to_string()),
+    }
+}
+
+/// Encode one instruction (plus any trailing words it needs) into machine code.
+fn encode(mnemonic: &str, operands: &[Operand], next_pc: u16, labels: &HashMap<String, u16>) -> Result<Vec<u16>, String> {
+    if mnemonic == "NOP" {
+        return Ok(vec![NOP_WORD]);
+    }
+    if mnemonic == "HALT" {
+        return Ok(vec![HALT_WORD]);
+    }
+    if mnemonic == "JMP" {
+        let addr = resolve(&operands[0], labels)? as u16;
+        return Ok(vec![(J_TYPE_OP << 12) | (addr & 0x0FFF)]);
+    }
+    if let Some((_, op)) = R_TYPE.iter().find(|(n, _)| *n == mnemonic) {
+        let rd = reg(&operands[0])?;
+        let rs = reg(&operands[1])?;
+        let rt = if operands.len() > 2 { reg(&operands[2])? } else { rs };
+        return Ok(vec![(op << 12) | ((rd as u16) << 8) | ((rs as u16) << 4) | (rt as u16)]);
+    }
+    if let Some((_, op)) = I_TYPE.iter().find(|(n, _)| *n == mnemonic) {
+        let rd = reg(&operands[0])?;
+        let imm = resolve(&operands[1], labels)?;
+        return Ok(vec![(op << 12) | ((rd as u16) << 8) | ((imm as u16) & 0xFF)]);
+    }
+    if let Some((_, op)) = M_TYPE.iter().find(|(n, _)| *n == mnemonic) {
+        let rd = reg(&operands[0])?;
+        let rs = reg(&operands[1])?;
+        let offset = if operands.len() > 2 { resolve(&operands[2], labels)? } else { 0 };
+        return Ok(vec![(op << 12) | ((rd as u16) << 8) | ((rs as u16) << 4) | ((offset as u16) & 0xF)]);
+    }
+    if let Some((_, op)) = B_TYPE.iter().find(|(n, _)| *n == mnemonic) {
+        let rd = reg(&operands[0])?;
+        let target = resolve(&operands[1], labels)?;
+        // Branch targets are given as absolute addresses; the CPU adds
+        // (imm8 as i8) * 2 to the already-advanced PC, so solve for imm8.
+        let byte_offset = target as i32 - next_pc as i32;
+        if byte_offset % 2 != 0 {
+            return Err(format!("branch target {:#06x} is not word-aligned relative to {:#06x}", target, next_pc));
+        }
+        let word_offset = byte_offset / 2;
+        if !(-128..=127).contains(&word_offset) {
+            return Err(format!("branch target out of range ({} words)", word_offset));
+        }
+        return Ok(vec![(op << 12) | ((rd as u16) << 8) | ((word_offset as i8 as u8) as u16)]);
+    }
+    if let Some((_, sub)) = EXT_SUB.iter().find(|(n, _)| *n == mnemonic) {
+        let base = (EXTENDED_OP << 12) | (sub << 8);
+        return match mnemonic {
+            "CALL" => {
+                let target = resolve(&operands[0], labels)? as u16;
+                Ok(vec![base, target])
+            }
+            "PUSH" | "POP" => {
+                let r = reg(&operands[0])?;
+                Ok(vec![base, (r as u16) << 8])
+            }
+            _ => Ok(vec![base]), // RET, RTI take no operands
+        };
+    }
+
+    Err(format!("unknown mnemonic '{}'", mnemonic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_after_push_resolves_past_both_words() {
+        let program = assemble("PUSH R1\nloop: HALT\nJMP loop").unwrap();
+
+        // PUSH encodes to 2 words, so `loop` must sit at address 4, not 2.
+        assert_eq!(program.words.len(), 4);
+        assert_eq!(program.words[2], HALT_WORD);
+        assert_eq!(program.words[3], (J_TYPE_OP << 12) | 0x004);
+    }
+
+    #[test]
+    fn label_after_pop_resolves_past_both_words() {
+        let program = assemble("POP R1\nloop: HALT\nJMP loop").unwrap();
+
+        assert_eq!(program.words.len(), 4);
+        assert_eq!(program.words[2], HALT_WORD);
+        assert_eq!(program.words[3], (J_TYPE_OP << 12) | 0x004);
+    }
+}
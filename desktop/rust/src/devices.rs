@@ -0,0 +1,133 @@
+// ============================================================================
+// desktop/rust/src/devices.rs
+// Memory-mapped I/O devices for the CVERE VM's device bus
+// ============================================================================
+
+use std::collections::VecDeque;
+
+/// A memory-mapped peripheral. Registers are addressed by a byte offset
+/// relative to the device's base address in the bus's MMIO window.
+pub trait Device {
+    fn read_reg(&mut self, offset: u16) -> u8;
+    fn write_reg(&mut self, offset: u16, value: u8);
+
+    /// Human-readable identifier, used in bus/dump listings.
+    fn name(&self) -> &'static str;
+
+    /// Short status line for `dump`/REPL output.
+    fn describe(&self) -> String;
+
+    /// Advance the device by one VM cycle. Returns `true` the instant it
+    /// wants to raise an interrupt/trap (e.g. a timer reaching zero).
+    fn tick(&mut self) -> bool {
+        false
+    }
+}
+
+/// Console port: writes to register 0 emit a character to stdout, reads
+/// from register 0 pull from a queued input buffer (0 if empty).
+pub struct ConsoleDevice {
+    input: VecDeque<u8>,
+    bytes_written: u64,
+}
+
+impl ConsoleDevice {
+    pub fn new() -> Self {
+        ConsoleDevice { input: VecDeque::new(), bytes_written: 0 }
+    }
+
+    /// Queue bytes for the guest to read back via register 0.
+    pub fn queue_input(&mut self, bytes: &[u8]) {
+        self.input.extend(bytes.iter().copied());
+    }
+}
+
+impl Device for ConsoleDevice {
+    fn read_reg(&mut self, offset: u16) -> u8 {
+        match offset {
+            0 => self.input.pop_front().unwrap_or(0),
+            1 => if self.input.is_empty() { 0 } else { 1 }, // status: input-available flag
+            _ => 0,
+        }
+    }
+
+    fn write_reg(&mut self, offset: u16, value: u8) {
+        if offset == 0 {
+            print!("{}", value as char);
+            self.bytes_written += 1;
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "console"
+    }
+
+    fn describe(&self) -> String {
+        format!("console: {} bytes written, {} queued for input", self.bytes_written, self.input.len())
+    }
+}
+
+/// Countdown timer. Register layout: 0/1 = current counter (lo/hi byte),
+/// 2/3 = reload value (lo/hi byte). Decrements once per `tick()`; on
+/// wraparound it reloads from the configured value and reports that it
+/// fired so the VM can deliver a timer trap.
+pub struct TimerDevice {
+    counter: u16,
+    reload: u16,
+}
+
+impl TimerDevice {
+    pub fn new() -> Self {
+        TimerDevice { counter: 0, reload: 0 }
+    }
+
+    pub fn set_reload(&mut self, reload: u16) {
+        self.reload = reload;
+        self.counter = reload;
+    }
+}
+
+impl Device for TimerDevice {
+    fn read_reg(&mut self, offset: u16) -> u8 {
+        match offset {
+            0 => (self.counter & 0xFF) as u8,
+            1 => (self.counter >> 8) as u8,
+            2 => (self.reload & 0xFF) as u8,
+            3 => (self.reload >> 8) as u8,
+            _ => 0,
+        }
+    }
+
+    fn write_reg(&mut self, offset: u16, value: u8) {
+        match offset {
+            0 => self.counter = (self.counter & 0xFF00) | value as u16,
+            1 => self.counter = (self.counter & 0x00FF) | ((value as u16) << 8),
+            2 => self.reload = (self.reload & 0xFF00) | value as u16,
+            3 => self.reload = (self.reload & 0x00FF) | ((value as u16) << 8),
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "timer"
+    }
+
+    fn describe(&self) -> String {
+        format!("timer: counter=0x{:04X} reload=0x{:04X}", self.counter, self.reload)
+    }
+
+    fn tick(&mut self) -> bool {
+        // A reload of 0 means the timer hasn't been armed; stay quiescent
+        // rather than firing on every single cycle.
+        if self.reload == 0 {
+            return false;
+        }
+        if self.counter == 0 {
+            self.counter = self.reload;
+            true
+        } else {
+            self.counter = self.counter.wrapping_sub(1);
+            false
+        }
+    }
+}
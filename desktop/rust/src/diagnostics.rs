@@ -0,0 +1,97 @@
+// ============================================================================
+// desktop/rust/src/diagnostics.rs
+// Span-aware diagnostics shared by the hex loader and the assembler
+// ============================================================================
+
+use std::fmt;
+
+/// Location of an offending token within a source file.
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+impl Span {
+    /// Build a span pointing at `token`'s first occurrence inside `line_text`,
+    /// falling back to the whole line if the token can't be found verbatim
+    /// (e.g. it was synthesized, such as an uppercased mnemonic).
+    pub fn locate(line: usize, line_text: &str, token: &str) -> Span {
+        match line_text.find(token) {
+            Some(column) => Span { line, column, length: token.len().max(1) },
+            None => Span { line, column: 0, length: line_text.trim_end().len().max(1) },
+        }
+    }
+
+    pub fn whole_line(line: usize, line_text: &str) -> Span {
+        Span { line, column: 0, length: line_text.trim_end().len().max(1) }
+    }
+}
+
+/// A single diagnostic: a message anchored to a span, plus the source line
+/// it occurred on so it can be rendered with a caret underline.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+    pub source_line: String,
+}
+
+impl Diagnostic {
+    pub fn new(span: Span, message: impl Into<String>, source_line: impl Into<String>) -> Self {
+        Diagnostic { span, message: message.into(), source_line: source_line.into() }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "  --> line {}", self.span.line)?;
+        writeln!(f, "   | {}", self.source_line)?;
+        write!(f, "   | {}{}", " ".repeat(self.span.column), "^".repeat(self.span.length))
+    }
+}
+
+/// Accumulates diagnostics across a full pass instead of bailing on the
+/// first error, so a source file's problems can be reported all at once.
+#[derive(Debug, Default)]
+pub struct DiagnosticBag {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl DiagnosticBag {
+    pub fn new() -> Self {
+        DiagnosticBag { diagnostics: Vec::new() }
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+}
+
+impl fmt::Display for DiagnosticBag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}\n", diagnostic)?;
+        }
+        let count = self.diagnostics.len();
+        write!(f, "{} error{} emitted", count, if count == 1 { "" } else { "s" })
+    }
+}
@@ -3,12 +3,24 @@
 // Main library file - re-exports modules
 // ============================================================================
 
+pub mod assembler;
+pub mod devices;
+pub mod diagnostics;
 pub mod memory;
+pub mod object;
 pub mod registers;
 pub mod decoder;
+pub mod syscall;
+pub mod trap;
 pub mod vm;
 
 pub use vm::CVEREVM;
 pub use memory::Memory;
+pub use devices::{ConsoleDevice, Device, TimerDevice};
+pub use object::{ObjectFile, Segment};
+pub use syscall::{DefaultSyscallHandler, SyscallHandler};
 pub use registers::{RegisterFile, StatusFlags};
 pub use decoder::{InstructionDecoder, DecodedInstruction, InstructionFormat};
+pub use trap::Trap;
+pub use assembler::{assemble, AssembledProgram};
+pub use diagnostics::{Diagnostic, DiagnosticBag, Span};
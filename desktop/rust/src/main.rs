@@ -3,15 +3,25 @@
 // Updated main entry point using the new module structure
 // ============================================================================
 
+mod assembler;
+mod devices;
+mod diagnostics;
 mod memory;
+mod object;
 mod registers;
 mod decoder;
+mod syscall;
+mod trap;
 mod vm;
 
 use vm::CVEREVM;
+use trap::Trap;
+use diagnostics::{Diagnostic, DiagnosticBag, Span};
+use object::{ObjectFile, Segment};
 use std::env;
 use std::fs;
 use std::io::{self, Write};
+use std::path::Path;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -36,6 +46,33 @@ fn main() {
             }
             trace_program(&args[2]);
         }
+        "asm" => {
+            if args.len() < 3 {
+                eprintln!("Error: Missing source file");
+                return;
+            }
+            assemble_program(&args[2]);
+        }
+        "build" => {
+            if args.len() < 3 {
+                eprintln!("Error: Missing source file");
+                return;
+            }
+            let output = match args.iter().position(|a| a == "-o") {
+                Some(i) => match args.get(i + 1) {
+                    Some(path) => path.clone(),
+                    None => {
+                        eprintln!("Error: -o requires an output path");
+                        return;
+                    }
+                },
+                None => {
+                    eprintln!("Error: Missing -o <file.cvere>");
+                    return;
+                }
+            };
+            build_program(&args[2], &output);
+        }
         "test" => {
             run_tests();
         }
@@ -53,21 +90,50 @@ fn print_usage() {
     println!("CVERE Virtual Machine");
     println!();
     println!("Usage:");
-    println!("  cvere run <file>    - Run a program from file");
+    println!("  cvere run <file>    - Run a program from file (.s assembly or raw hex)");
     println!("  cvere trace <file>  - Run with execution tracing");
+    println!("  cvere asm <file.s>  - Assemble a source file and print its hex encoding");
+    println!("  cvere build <file.s> -o <file.cvere> - Assemble and emit a binary object file");
     println!("  cvere test          - Run built-in tests");
     println!("  cvere repl          - Start interactive REPL");
 }
 
-fn run_program(filename: &str) {
-    match load_program_from_file(filename) {
+/// Assembly sources are auto-detected by extension; anything else is
+/// treated as the original newline-delimited hex format.
+fn is_assembly_source(filename: &str) -> bool {
+    matches!(Path::new(filename).extension().and_then(|e| e.to_str()), Some("s") | Some("asm"))
+}
+
+fn assemble_program(filename: &str) {
+    let source = match fs::read_to_string(filename) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error: Failed to read file: {}", e);
+            return;
+        }
+    };
+
+    match assembler::assemble(&source) {
         Ok(program) => {
-            let mut vm = CVEREVM::new();
-            
-            if let Err(e) = vm.load_program(&program, 0) {
-                eprintln!("Error loading program: {}", e);
-                return;
+            println!("; origin 0x{:04X}", program.origin);
+            for word in program.words {
+                println!("0x{:04X}", word);
+            }
+        }
+        Err(diagnostics) => {
+            for d in &diagnostics {
+                eprintln!("{}\n", d);
             }
+            eprintln!("{} error{} emitted", diagnostics.len(), if diagnostics.len() == 1 { "" } else { "s" });
+        }
+    }
+}
+
+fn run_program(filename: &str) {
+    let mut vm = CVEREVM::new();
+    match load_into_vm(&mut vm, filename) {
+        Ok(entry) => {
+            vm.registers.pc = entry;
 
             println!("Running program from: {}", filename);
             match vm.run(100000) {
@@ -75,8 +141,8 @@ fn run_program(filename: &str) {
                     println!("\nProgram completed in {} cycles", cycles);
                     println!("{}", vm);
                 }
-                Err(e) => {
-                    eprintln!("Runtime error: {}", e);
+                Err(trap) => {
+                    print_unhandled_trap(&trap, &vm);
                     println!("{}", vm);
                 }
             }
@@ -86,27 +152,24 @@ fn run_program(filename: &str) {
 }
 
 fn trace_program(filename: &str) {
-    match load_program_from_file(filename) {
-        Ok(program) => {
-            let mut vm = CVEREVM::new();
-            vm.set_trace(true);
-            
-            if let Err(e) = vm.load_program(&program, 0) {
-                eprintln!("Error loading program: {}", e);
-                return;
-            }
+    let mut vm = CVEREVM::new();
+    vm.set_trace(true);
+    match load_into_vm(&mut vm, filename) {
+        Ok(entry) => {
+            vm.registers.pc = entry;
 
             println!("Tracing program from: {}", filename);
             println!("==========================================");
-            
+
             match vm.run(100000) {
                 Ok(cycles) => {
                     println!("==========================================");
                     println!("\nProgram completed in {} cycles", cycles);
                     println!("{}", vm);
                 }
-                Err(e) => {
-                    eprintln!("\nRuntime error: {}", e);
+                Err(trap) => {
+                    println!("==========================================");
+                    print_unhandled_trap(&trap, &vm);
                     println!("{}", vm);
                 }
             }
@@ -115,14 +178,86 @@ fn trace_program(filename: &str) {
     }
 }
 
-fn load_program_from_file(filename: &str) -> Result<Vec<u16>, String> {
-    let contents = fs::read_to_string(filename)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+/// Assemble `filename` and write it out as a binary object file at `output`.
+fn build_program(filename: &str, output: &str) {
+    let source = match fs::read_to_string(filename) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error: Failed to read file: {}", e);
+            return;
+        }
+    };
+
+    let program = match assembler::assemble(&source) {
+        Ok(program) => program,
+        Err(diagnostics) => {
+            eprintln!("{}", render_diagnostics(&diagnostics));
+            return;
+        }
+    };
+
+    let object = ObjectFile {
+        entry: program.origin,
+        segments: vec![Segment { address: program.origin, words: program.words }],
+    };
+
+    let mut bytes = Vec::new();
+    object.write_to(&mut bytes);
+
+    if let Err(e) = fs::write(output, &bytes) {
+        eprintln!("Error: Failed to write {}: {}", output, e);
+        return;
+    }
+
+    println!("Wrote {} ({} bytes)", output, bytes.len());
+}
+
+/// Print an unhandled trap's symbolic name, the disassembled instruction at
+/// its PC, and a caret-annotated hex view of the surrounding memory.
+fn print_unhandled_trap(trap: &Trap, vm: &CVEREVM) {
+    eprintln!("Unhandled trap: {}", trap.name());
+    eprintln!("{}", trap.render(&vm.memory));
+}
+
+/// Load a program file into `vm`, auto-detecting its format, and return the
+/// address execution should start at. Binary object files (produced by
+/// `cvere build`) are detected by their magic bytes and may contain
+/// multiple segments loaded at independent addresses; everything else is
+/// read as text (`.s`/`.asm` assembly, or the original newline-delimited
+/// hex format) and treated as a single segment.
+fn load_into_vm(vm: &mut CVEREVM, filename: &str) -> Result<u16, String> {
+    let raw = fs::read(filename).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    if object::is_object_file(&raw) {
+        let object = ObjectFile::read_from(&raw)?;
+        for segment in &object.segments {
+            vm.load_program(&segment.words, segment.address).map_err(|e| e.to_string())?;
+        }
+        return Ok(object.entry);
+    }
+
+    let contents = String::from_utf8(raw).map_err(|_| "file is neither a CVERE object file nor valid UTF-8 text".to_string())?;
+    let (program, origin) = load_text_program(filename, &contents)?;
+    vm.load_program(&program, origin).map_err(|e| e.to_string())?;
+    Ok(origin)
+}
+
+/// Parse a text-format program: assembly sources (`.s`/`.asm`) are
+/// assembled; everything else is read as the original newline-delimited
+/// hex format.
+fn load_text_program(filename: &str, contents: &str) -> Result<(Vec<u16>, u16), String> {
+    if is_assembly_source(filename) {
+        return assembler::assemble(&contents)
+            .map(|p| (p.words, p.origin))
+            .map_err(|diagnostics| render_diagnostics(&diagnostics));
+    }
 
     let mut program = Vec::new();
-    
-    for line in contents.lines() {
-        let line = line.trim();
+    let mut errors = DiagnosticBag::new();
+
+    for (i, raw_line) in contents.lines().enumerate() {
+        let line_no = i + 1;
+        let line = raw_line.trim();
         if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
             continue;
         }
@@ -131,11 +266,29 @@ fn load_program_from_file(filename: &str) -> Result<Vec<u16>, String> {
         let hex = line.trim_start_matches("0x").trim_start_matches("0X");
         match u16::from_str_radix(hex, 16) {
             Ok(value) => program.push(value),
-            Err(_) => return Err(format!("Invalid hex value: {}", line)),
+            Err(_) => errors.push(Diagnostic::new(
+                Span::locate(line_no, raw_line, line),
+                format!("invalid hex value '{}'", line),
+                raw_line.to_string(),
+            )),
         }
     }
 
-    Ok(program)
+    if !errors.is_empty() {
+        return Err(render_diagnostics(&errors.into_vec()));
+    }
+
+    Ok((program, 0))
+}
+
+fn render_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    let mut out = String::new();
+    for d in diagnostics {
+        out.push_str(&d.to_string());
+        out.push_str("\n\n");
+    }
+    out.push_str(&format!("{} error{} emitted", diagnostics.len(), if diagnostics.len() == 1 { "" } else { "s" }));
+    out
 }
 
 fn repl() {
@@ -3,10 +3,188 @@
 // Memory management module for CVERE VM
 // ============================================================================
 
+use crate::devices::Device;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Size in bytes of a single virtual page / physical frame.
+pub const PAGE_SIZE: usize = 256;
+
+/// Kind of access that was being attempted when a fault occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+    Execute,
+}
+
+impl fmt::Display for AccessKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AccessKind::Read => write!(f, "read"),
+            AccessKind::Write => write!(f, "write"),
+            AccessKind::Execute => write!(f, "execute"),
+        }
+    }
+}
+
+/// Why a memory access was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryFaultReason {
+    /// Address has no page table entry.
+    Unmapped,
+    /// Page is mapped but doesn't permit the attempted access kind.
+    PermissionDenied,
+    /// Address falls outside the backing store entirely.
+    OutOfBounds,
+}
+
+impl fmt::Display for MemoryFaultReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MemoryFaultReason::Unmapped => write!(f, "unmapped page"),
+            MemoryFaultReason::PermissionDenied => write!(f, "permission denied"),
+            MemoryFaultReason::OutOfBounds => write!(f, "out of bounds"),
+        }
+    }
+}
+
+/// Structured description of a failed memory access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryFault {
+    pub addr: usize,
+    pub access_kind: AccessKind,
+    pub reason: MemoryFaultReason,
+}
+
+impl fmt::Display for MemoryFault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "memory {} fault at 0x{:04X}: {}",
+            self.access_kind, self.addr, self.reason
+        )
+    }
+}
+
+// Existing callers propagate errors as `String` via `?`; this lets the
+// richer fault type flow through those call sites unchanged.
+impl From<MemoryFault> for String {
+    fn from(fault: MemoryFault) -> String {
+        fault.to_string()
+    }
+}
+
+/// Why `Memory::read_bytes`'s zero-copy fast path couldn't serve a
+/// request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkReadError {
+    /// The access itself is invalid - see the wrapped `MemoryFault`.
+    Fault(MemoryFault),
+    /// The range is addressable but isn't one contiguous run of plain
+    /// backing memory (it overlaps a device register, crosses a
+    /// permission-region boundary, or spans more than one page while
+    /// paging is on), so it can't be handed back as
+    /// a single borrowed slice. Not a fault - retry with
+    /// `read_bytes_copied`, which walks it region by region.
+    NotContiguous,
+}
+
+impl fmt::Display for BulkReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BulkReadError::Fault(fault) => write!(f, "{}", fault),
+            BulkReadError::NotContiguous => write!(f, "range is not one contiguous run of plain memory"),
+        }
+    }
+}
+
+impl From<MemoryFault> for BulkReadError {
+    fn from(fault: MemoryFault) -> Self {
+        BulkReadError::Fault(fault)
+    }
+}
+
+/// Read/write/execute permission bits for a single page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PagePermissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl PagePermissions {
+    pub fn rwx() -> Self {
+        PagePermissions { read: true, write: true, execute: true }
+    }
+
+    pub fn read_only() -> Self {
+        PagePermissions { read: true, write: false, execute: false }
+    }
+
+    pub fn read_execute() -> Self {
+        PagePermissions { read: true, write: false, execute: true }
+    }
+
+    pub fn read_write() -> Self {
+        PagePermissions { read: true, write: true, execute: false }
+    }
+
+    fn allows(&self, kind: AccessKind) -> bool {
+        match kind {
+            AccessKind::Read => self.read,
+            AccessKind::Write => self.write,
+            AccessKind::Execute => self.execute,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PageTableEntry {
+    frame: usize,
+    perms: PagePermissions,
+}
+
+/// A byte-range permission region registered via `Memory::protect`.
+/// Unlike the page table above, these apply regardless of whether paging
+/// is enabled, and are checked against every access kind including
+/// `Execute` (which the page table's own translation never tests, since
+/// nothing routes `fetch` through it today).
+#[derive(Debug, Clone, Copy)]
+struct PermissionRegion {
+    start: usize,
+    end: usize,
+    perms: PagePermissions,
+}
+
+/// A device registered onto the bus at a fixed byte-addressed window.
+struct MappedDevice {
+    base: usize,
+    len: usize,
+    device: Box<dyn Device>,
+}
+
 /// Memory subsystem for CVERE VM
 pub struct Memory {
     data: Vec<u8>,
     size: usize,
+
+    /// When `false` (the default), addresses are identity-mapped and only
+    /// bounds-checked, matching the original flat-memory behavior.
+    paging_enabled: bool,
+    page_table: HashMap<usize, PageTableEntry>,
+
+    /// Memory-mapped I/O devices. Reads/writes that land inside a device's
+    /// window are dispatched to it instead of touching `data`.
+    devices: Vec<MappedDevice>,
+
+    /// Byte-range permission regions registered via `protect`. Checked on
+    /// every access to plain memory (device windows bypass them) in
+    /// reverse registration order, last-registered first, so a later
+    /// `protect` call narrowing part of an earlier one
+    /// takes precedence. Empty by default, matching the original
+    /// unrestricted behavior.
+    regions: Vec<PermissionRegion>,
 }
 
 impl Memory {
@@ -15,64 +193,388 @@ impl Memory {
         Memory {
             data: vec![0; size],
             size,
+            paging_enabled: false,
+            page_table: HashMap::new(),
+            devices: Vec::new(),
+            regions: Vec::new(),
+        }
+    }
+
+    // ========================================================================
+    // MEMORY-MAPPED I/O
+    // ========================================================================
+
+    /// Reserve `[base, base + len)` for a device, so accesses in that range
+    /// are dispatched to it instead of reading/writing backing RAM.
+    pub fn register_device(&mut self, base: usize, len: usize, device: Box<dyn Device>) {
+        self.devices.push(MappedDevice { base, len, device });
+    }
+
+    fn device_at(&mut self, address: usize) -> Option<(&mut Box<dyn Device>, u16)> {
+        for mapped in &mut self.devices {
+            if address >= mapped.base && address < mapped.base + mapped.len {
+                return Some((&mut mapped.device, (address - mapped.base) as u16));
+            }
+        }
+        None
+    }
+
+    /// `&self` form of `device_at` for callers that only need to know
+    /// whether `address` lands inside some device's window, not a live
+    /// handle to dispatch `read_reg`/`write_reg` through.
+    fn address_in_device(&self, address: usize) -> bool {
+        self.devices.iter().any(|mapped| address >= mapped.base && address < mapped.base + mapped.len)
+    }
+
+    /// Advance every device by one cycle. Returns the names of devices that
+    /// want to raise an interrupt/trap this cycle (e.g. a timer wraparound).
+    pub fn tick_devices(&mut self) -> Vec<&'static str> {
+        self.devices
+            .iter_mut()
+            .filter_map(|mapped| if mapped.device.tick() { Some(mapped.device.name()) } else { None })
+            .collect()
+    }
+
+    /// Status lines for every registered device, used by the REPL's `dump`.
+    pub fn device_dump(&self) -> String {
+        if self.devices.is_empty() {
+            return String::new();
+        }
+        let mut result = String::new();
+        for mapped in &self.devices {
+            result.push_str(&format!("  0x{:04X}: {}\n", mapped.base, mapped.device.describe()));
+        }
+        result
+    }
+
+    // ========================================================================
+    // PAGING / MMU
+    // ========================================================================
+
+    /// Enable paged address translation. Until a page is mapped, any access
+    /// to it faults with `MemoryFaultReason::Unmapped`.
+    pub fn enable_paging(&mut self) {
+        self.paging_enabled = true;
+    }
+
+    /// Fall back to flat, identity-mapped addressing (the original behavior).
+    pub fn disable_paging(&mut self) {
+        self.paging_enabled = false;
+    }
+
+    pub fn paging_enabled(&self) -> bool {
+        self.paging_enabled
+    }
+
+    /// Map virtual page `vpn` onto physical frame `frame` with the given
+    /// permissions. Both are page-sized units, not byte addresses.
+    pub fn map_page(&mut self, vpn: usize, frame: usize, perms: PagePermissions) -> Result<(), MemoryFault> {
+        if (frame + 1) * PAGE_SIZE > self.size {
+            return Err(MemoryFault {
+                addr: frame * PAGE_SIZE,
+                access_kind: AccessKind::Read,
+                reason: MemoryFaultReason::OutOfBounds,
+            });
+        }
+        self.page_table.insert(vpn, PageTableEntry { frame, perms });
+        Ok(())
+    }
+
+    /// Remove the mapping for virtual page `vpn`, if any.
+    pub fn unmap_page(&mut self, vpn: usize) {
+        self.page_table.remove(&vpn);
+    }
+
+    /// Change the permission bits of an already-mapped page.
+    pub fn set_permissions(&mut self, vpn: usize, perms: PagePermissions) -> Result<(), MemoryFault> {
+        match self.page_table.get_mut(&vpn) {
+            Some(entry) => {
+                entry.perms = perms;
+                Ok(())
+            }
+            None => Err(MemoryFault {
+                addr: vpn * PAGE_SIZE,
+                access_kind: AccessKind::Write,
+                reason: MemoryFaultReason::Unmapped,
+            }),
+        }
+    }
+
+    /// Translate a virtual byte address to a physical one, checking
+    /// permissions for `kind` along the way.
+    fn translate(&self, address: usize, kind: AccessKind) -> Result<usize, MemoryFault> {
+        self.check_region(address, kind)?;
+
+        if !self.paging_enabled {
+            if address >= self.size {
+                return Err(MemoryFault { addr: address, access_kind: kind, reason: MemoryFaultReason::OutOfBounds });
+            }
+            return Ok(address);
+        }
+
+        let vpn = address / PAGE_SIZE;
+        let offset = address % PAGE_SIZE;
+
+        match self.page_table.get(&vpn) {
+            None => Err(MemoryFault { addr: address, access_kind: kind, reason: MemoryFaultReason::Unmapped }),
+            Some(entry) => {
+                if !entry.perms.allows(kind) {
+                    return Err(MemoryFault { addr: address, access_kind: kind, reason: MemoryFaultReason::PermissionDenied });
+                }
+                let physical = entry.frame * PAGE_SIZE + offset;
+                if physical >= self.size {
+                    return Err(MemoryFault { addr: address, access_kind: kind, reason: MemoryFaultReason::OutOfBounds });
+                }
+                Ok(physical)
+            }
         }
     }
 
+    // ========================================================================
+    // PERMISSION REGIONS (W^X / read-only code)
+    // ========================================================================
+
+    /// Register `[start, start + len)` as permitting only `perms`. Later
+    /// calls take precedence over earlier ones where ranges overlap, so
+    /// e.g. marking a code region read+execute after a blanket
+    /// read+write region leaves the rest of memory writable but carves
+    /// the code out as not.
+    pub fn protect(&mut self, start: usize, len: usize, perms: PagePermissions) {
+        self.regions.push(PermissionRegion { start, end: start + len, perms });
+    }
+
+    /// Remove every registered permission region, returning to the
+    /// unrestricted default.
+    pub fn clear_protection(&mut self) {
+        self.regions.clear();
+    }
+
+    /// Permissions in effect for `address`, from the most recently
+    /// registered region that covers it. `None` means no region covers
+    /// it, i.e. unrestricted.
+    fn region_perms(&self, address: usize) -> Option<PagePermissions> {
+        self.regions.iter().rev().find(|r| address >= r.start && address < r.end).map(|r| r.perms)
+    }
+
+    /// Check `address` against the registered permission regions for
+    /// `kind`, independent of paging. Used by `translate` for
+    /// read/write and by `fetch_word` for instruction fetch, since the
+    /// latter never goes through `translate` at all.
+    fn check_region(&self, address: usize, kind: AccessKind) -> Result<(), MemoryFault> {
+        match self.region_perms(address) {
+            Some(perms) if !perms.allows(kind) => {
+                Err(MemoryFault { addr: address, access_kind: kind, reason: MemoryFaultReason::PermissionDenied })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // ========================================================================
+    // BYTE / WORD ACCESS
+    // ========================================================================
+
     /// Read a byte from memory
-    pub fn read_byte(&self, address: usize) -> Result<u8, String> {
-        if address >= self.size {
-            return Err(format!("Memory read out of bounds: 0x{:04X}", address));
+    pub fn read_byte(&mut self, address: usize) -> Result<u8, MemoryFault> {
+        if let Some((device, offset)) = self.device_at(address) {
+            return Ok(device.read_reg(offset));
         }
-        Ok(self.data[address])
+        let physical = self.translate(address, AccessKind::Read)?;
+        Ok(self.data[physical])
     }
 
     /// Write a byte to memory
-    pub fn write_byte(&mut self, address: usize, value: u8) -> Result<(), String> {
-        if address >= self.size {
-            return Err(format!("Memory write out of bounds: 0x{:04X}", address));
+    pub fn write_byte(&mut self, address: usize, value: u8) -> Result<(), MemoryFault> {
+        if let Some((device, offset)) = self.device_at(address) {
+            device.write_reg(offset, value);
+            return Ok(());
         }
-        self.data[address] = value;
+        let physical = self.translate(address, AccessKind::Write)?;
+        self.data[physical] = value;
         Ok(())
     }
 
     /// Read a 16-bit word (little-endian)
-    pub fn read_word(&self, address: usize) -> Result<u16, String> {
-        if address + 1 >= self.size {
-            return Err(format!("Memory word read out of bounds: 0x{:04X}", address));
-        }
-        let low = self.data[address] as u16;
-        let high = self.data[address + 1] as u16;
+    pub fn read_word(&mut self, address: usize) -> Result<u16, MemoryFault> {
+        let low = self.read_byte(address)? as u16;
+        let high = self.read_byte(address + 1)? as u16;
         Ok((high << 8) | low)
     }
 
+    /// Read the instruction word at `address`, the way `read_word` does,
+    /// but additionally requiring the region it lands in (if any) to be
+    /// executable. `read_word`/`read_byte` only ever check `Read`, so
+    /// this is the one path that exercises `AccessKind::Execute`.
+    pub fn fetch_word(&mut self, address: usize) -> Result<u16, MemoryFault> {
+        self.check_region(address, AccessKind::Execute)?;
+        self.check_region(address + 1, AccessKind::Execute)?;
+        self.read_word(address)
+    }
+
     /// Write a 16-bit word (little-endian)
-    pub fn write_word(&mut self, address: usize, value: u16) -> Result<(), String> {
-        if address + 1 >= self.size {
-            return Err(format!("Memory word write out of bounds: 0x{:04X}", address));
+    pub fn write_word(&mut self, address: usize, value: u16) -> Result<(), MemoryFault> {
+        self.write_byte(address, (value & 0xFF) as u8)?;
+        self.write_byte(address + 1, (value >> 8) as u8)?;
+        Ok(())
+    }
+
+    // ========================================================================
+    // BULK TRANSFERS
+    // ========================================================================
+
+    /// How many bytes starting at `addr` can be copied as one contiguous
+    /// run: bounded by whichever comes first among the requested length,
+    /// the end of the containing page (when paging is enabled) or of
+    /// backing memory (when it isn't), and the start of the next device
+    /// window. This is what lets `read_bytes`/`write_bytes` turn a large
+    /// transfer into a handful of slice copies instead of a bounds check
+    /// and assignment per byte.
+    fn contiguous_run(&self, addr: usize, remaining: usize) -> usize {
+        let region_end = if self.paging_enabled {
+            (addr / PAGE_SIZE + 1) * PAGE_SIZE
+        } else {
+            self.size
+        };
+        let mut limit = region_end.min(addr + remaining);
+        for mapped in &self.devices {
+            if mapped.base > addr && mapped.base < limit {
+                limit = mapped.base;
+            }
+        }
+        for region in &self.regions {
+            for edge in [region.start, region.end] {
+                if edge > addr && edge < limit {
+                    limit = edge;
+                }
+            }
+        }
+        limit.saturating_sub(addr)
+    }
+
+    /// Borrowed, zero-copy view of `len` bytes starting at `addr`, for
+    /// DMA-style transfers that only need to read memory, not dispatch
+    /// a device or walk multiple physical runs. Succeeds only for the
+    /// common case - `addr..addr+len` is one contiguous run of plain
+    /// backing memory, per `contiguous_run` (no device window, no
+    /// permission-region boundary, and no page crossing while paging is
+    /// on). A range that isn't has to be
+    /// read one region/register at a time, which can't be expressed as
+    /// a single borrowed slice; call `read_bytes_copied` for that case.
+    pub fn read_bytes(&self, addr: usize, len: usize) -> Result<&[u8], BulkReadError> {
+        if len == 0 {
+            return Ok(&[]);
+        }
+        if self.address_in_device(addr) || self.contiguous_run(addr, len) < len {
+            return Err(BulkReadError::NotContiguous);
+        }
+        let physical = self.translate(addr, AccessKind::Read)?;
+        Ok(&self.data[physical..physical + len])
+    }
+
+    /// Read `len` bytes starting at `addr` as a bounded number of contiguous
+    /// transfers rather than `len` individual bounds checks. Devices on the
+    /// range are still dispatched through `read_reg`, one register at a
+    /// time. Always succeeds at the cost of an owned copy; prefer
+    /// `read_bytes` when the range is known to be plain contiguous memory.
+    pub fn read_bytes_copied(&mut self, addr: usize, len: usize) -> Result<Vec<u8>, MemoryFault> {
+        let mut out = Vec::with_capacity(len);
+        let mut cursor = addr;
+        let mut remaining = len;
+
+        while remaining > 0 {
+            if let Some((device, offset)) = self.device_at(cursor) {
+                out.push(device.read_reg(offset));
+                cursor += 1;
+                remaining -= 1;
+                continue;
+            }
+
+            let run = self.contiguous_run(cursor, remaining).max(1);
+            let physical = self.translate(cursor, AccessKind::Read)?;
+            out.extend_from_slice(&self.data[physical..physical + run]);
+            cursor += run;
+            remaining -= run;
         }
-        self.data[address] = (value & 0xFF) as u8;
-        self.data[address + 1] = (value >> 8) as u8;
+
+        Ok(out)
+    }
+
+    /// Write `bytes` starting at `addr`, validating and copying each
+    /// contiguous run in one pass instead of one `write_byte` per element.
+    pub fn write_bytes(&mut self, addr: usize, bytes: &[u8]) -> Result<(), MemoryFault> {
+        let mut cursor = addr;
+        let mut offset_in_bytes = 0;
+        let mut remaining = bytes.len();
+
+        while remaining > 0 {
+            if let Some((device, reg_offset)) = self.device_at(cursor) {
+                device.write_reg(reg_offset, bytes[offset_in_bytes]);
+                cursor += 1;
+                offset_in_bytes += 1;
+                remaining -= 1;
+                continue;
+            }
+
+            let run = self.contiguous_run(cursor, remaining).max(1);
+            let physical = self.translate(cursor, AccessKind::Write)?;
+            self.data[physical..physical + run].copy_from_slice(&bytes[offset_in_bytes..offset_in_bytes + run]);
+            cursor += run;
+            offset_in_bytes += run;
+            remaining -= run;
+        }
+
         Ok(())
     }
 
+    /// Write `words` as little-endian pairs starting at `addr`, handling the
+    /// endianness conversion in one pass before handing off to `write_bytes`.
+    pub fn write_words(&mut self, addr: usize, words: &[u16]) -> Result<(), MemoryFault> {
+        let mut bytes = Vec::with_capacity(words.len() * 2);
+        for &word in words {
+            bytes.push((word & 0xFF) as u8);
+            bytes.push((word >> 8) as u8);
+        }
+        self.write_bytes(addr, &bytes)
+    }
+
     /// Load program into memory starting at address
-    pub fn load_program(&mut self, program: &[u16], start_address: usize) -> Result<(), String> {
-        let mut addr = start_address;
-        for &instruction in program {
-            self.write_word(addr, instruction)?;
-            addr += 2;
+    pub fn load_program(&mut self, program: &[u16], start_address: usize) -> Result<(), MemoryFault> {
+        self.write_words(start_address, program)
+    }
+
+    /// Read a 16-bit word directly out of backing storage, bypassing
+    /// devices/paging/permissions. Used for diagnostics (disassembling the
+    /// instruction at a faulting PC) where we want to see what's actually
+    /// there rather than replay the access that faulted.
+    pub fn peek_word(&self, address: usize) -> Option<u16> {
+        if address + 1 >= self.size {
+            return None;
         }
-        Ok(())
+        Some(((self.data[address + 1] as u16) << 8) | self.data[address] as u16)
+    }
+
+    /// Hex-dump `[start, start + length)` with a `^^` caret under the byte
+    /// at `caret_addr`, for pointing at the exact address a trap fired on.
+    pub fn hex_window(&self, start: usize, length: usize, caret_addr: usize) -> String {
+        let end = (start + length).min(self.size);
+        let mut hex_line = format!("{:04X}: ", start);
+        let mut caret_line = "      ".to_string();
+
+        for addr in start..end {
+            hex_line.push_str(&format!("{:02X} ", self.data[addr]));
+            caret_line.push_str(if addr == caret_addr { "^^ " } else { "   " });
+        }
+
+        format!("{}\n{}\n", hex_line, caret_line)
     }
 
     /// Get memory dump as hex string
     pub fn dump(&self, start: usize, length: usize) -> String {
         let mut result = String::new();
         let end = std::cmp::min(start + length, self.size);
-        
+
         for addr in (start..end).step_by(16) {
             result.push_str(&format!("{:04X}: ", addr));
-            
+
             // Hex bytes
             for i in 0..16 {
                 if addr + i < end {
@@ -81,7 +583,7 @@ impl Memory {
                     result.push_str("   ");
                 }
             }
-            
+
             // ASCII representation
             result.push_str(" |");
             for i in 0..16 {
@@ -96,7 +598,7 @@ impl Memory {
             }
             result.push_str("|\n");
         }
-        
+
         result
     }
 
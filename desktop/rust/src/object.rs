@@ -0,0 +1,103 @@
+// ============================================================================
+// desktop/rust/src/object.rs
+// Binary object format for compiled CVERE programs
+// ============================================================================
+
+/// Magic bytes identifying a CVERE object file. Chosen so a raw hex-dump
+/// text file (which starts with an ASCII digit or `0x`) can never collide.
+pub const MAGIC: [u8; 4] = [0xC5, 0xE2, 0xE5, 0x01];
+
+/// Current object format version. Bump this if the header or segment
+/// layout ever changes incompatibly.
+pub const VERSION: u8 = 1;
+
+/// A contiguous block of words to be loaded at a fixed address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub address: u16,
+    pub words: Vec<u16>,
+}
+
+/// A parsed (or to-be-written) CVERE object file: an entry point plus the
+/// segments that make up the program's memory image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectFile {
+    pub entry: u16,
+    pub segments: Vec<Segment>,
+}
+
+/// Quick check for the magic bytes, used by `run`/`trace` to distinguish a
+/// compiled binary from the original text-based hex/assembly formats.
+pub fn is_object_file(data: &[u8]) -> bool {
+    data.len() >= MAGIC.len() && data[..MAGIC.len()] == MAGIC
+}
+
+impl ObjectFile {
+    /// Serialize to the on-disk layout:
+    /// `magic(4) | version(1) | entry(2 LE) | segment_count(2 LE)`,
+    /// followed by each segment as `address(2 LE) | word_count(2 LE) | words(2*N LE)`.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.entry.to_le_bytes());
+        out.extend_from_slice(&(self.segments.len() as u16).to_le_bytes());
+
+        for segment in &self.segments {
+            out.extend_from_slice(&segment.address.to_le_bytes());
+            out.extend_from_slice(&(segment.words.len() as u16).to_le_bytes());
+            for &word in &segment.words {
+                out.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+    }
+
+    /// Parse the layout written by `write_to`, rejecting truncated or
+    /// version-mismatched data with a descriptive error.
+    pub fn read_from(data: &[u8]) -> Result<ObjectFile, String> {
+        let mut cursor = 0usize;
+
+        let take = |cursor: &mut usize, len: usize, what: &str| -> Result<(), String> {
+            if *cursor + len > data.len() {
+                return Err(format!("truncated object file: expected {} bytes for {}", len, what));
+            }
+            *cursor += len;
+            Ok(())
+        };
+
+        if !is_object_file(data) {
+            return Err("not a CVERE object file: bad magic".to_string());
+        }
+        cursor += MAGIC.len();
+
+        take(&mut cursor, 1, "version")?;
+        let version = data[cursor - 1];
+        if version != VERSION {
+            return Err(format!("unsupported object file version {} (expected {})", version, VERSION));
+        }
+
+        take(&mut cursor, 2, "entry point")?;
+        let entry = u16::from_le_bytes([data[cursor - 2], data[cursor - 1]]);
+
+        take(&mut cursor, 2, "segment count")?;
+        let segment_count = u16::from_le_bytes([data[cursor - 2], data[cursor - 1]]);
+
+        let mut segments = Vec::with_capacity(segment_count as usize);
+        for _ in 0..segment_count {
+            take(&mut cursor, 2, "segment address")?;
+            let address = u16::from_le_bytes([data[cursor - 2], data[cursor - 1]]);
+
+            take(&mut cursor, 2, "segment word count")?;
+            let word_count = u16::from_le_bytes([data[cursor - 2], data[cursor - 1]]);
+
+            let mut words = Vec::with_capacity(word_count as usize);
+            for _ in 0..word_count {
+                take(&mut cursor, 2, "segment word")?;
+                words.push(u16::from_le_bytes([data[cursor - 2], data[cursor - 1]]));
+            }
+
+            segments.push(Segment { address, words });
+        }
+
+        Ok(ObjectFile { entry, segments })
+    }
+}
@@ -7,7 +7,14 @@
 pub struct RegisterFile {
     // General purpose registers R0-RF
     gp_regs: [u16; 16],
-    
+
+    // Floating-point register bank. The data bus is 16 bits wide, so each
+    // slot here holds one half of an IEEE-754 `f32`; a 32-bit value occupies
+    // an even/odd pair (low half in the even register, high half in the
+    // odd one), the same way `FLOAD`/`FSTORE` split a float across two
+    // consecutive memory words.
+    fp_regs: [u16; 16],
+
     // Special registers
     pub pc: u16,    // Program Counter
     pub sp: u16,    // Stack Pointer
@@ -20,6 +27,7 @@ impl RegisterFile {
     pub fn new() -> Self {
         RegisterFile {
             gp_regs: [0; 16],
+            fp_regs: [0; 16],
             pc: 0,
             sp: 0xFFFE,  // Stack grows downward
             lr: 0,
@@ -51,9 +59,45 @@ impl RegisterFile {
         }
     }
 
+    /// Read one 16-bit half of the floating-point register bank.
+    pub fn read_fp_half(&self, reg: u8) -> u16 {
+        if reg >= 16 {
+            return 0;
+        }
+        self.fp_regs[reg as usize]
+    }
+
+    /// Write one 16-bit half of the floating-point register bank.
+    pub fn write_fp_half(&mut self, reg: u8, value: u16) {
+        if reg >= 16 {
+            return;
+        }
+        self.fp_regs[reg as usize] = value;
+    }
+
+    /// Read a 32-bit float out of the register pair starting at `reg`
+    /// (rounded down to the nearest even index): `reg` holds the low half,
+    /// `reg + 1` the high half.
+    pub fn read_f32(&self, reg: u8) -> f32 {
+        let base = (reg & !1) as usize;
+        let lo = self.fp_regs[base] as u32;
+        let hi = self.fp_regs[base + 1] as u32;
+        f32::from_bits((hi << 16) | lo)
+    }
+
+    /// Write a 32-bit float into the register pair starting at `reg`
+    /// (rounded down to the nearest even index), low half first.
+    pub fn write_f32(&mut self, reg: u8, value: f32) {
+        let base = (reg & !1) as usize;
+        let bits = value.to_bits();
+        self.fp_regs[base] = (bits & 0xFFFF) as u16;
+        self.fp_regs[base + 1] = (bits >> 16) as u16;
+    }
+
     /// Reset all registers
     pub fn reset(&mut self) {
         self.gp_regs = [0; 16];
+        self.fp_regs = [0; 16];
         self.pc = 0;
         self.sp = 0xFFFE;
         self.lr = 0;
@@ -80,6 +124,13 @@ impl RegisterFile {
                 result.push('\n');
             }
         }
+        result.push_str("\nFloating-Point Registers:\n");
+        for i in (0..16).step_by(2) {
+            result.push_str(&format!("  F{:X}: {}", i, self.read_f32(i as u8)));
+            if (i + 2) % 8 == 0 {
+                result.push('\n');
+            }
+        }
         result.push_str(&format!("\nSpecial Registers:\n"));
         result.push_str(&format!("  PC: 0x{:04X}\n", self.pc));
         result.push_str(&format!("  SP: 0x{:04X}\n", self.sp));
@@ -87,20 +138,102 @@ impl RegisterFile {
         result.push_str(&format!("  SR: 0x{:04X} ", self.sr));
         
         let flags = self.get_flags();
-        result.push_str(&format!("[Z={} N={} C={} V={}]\n", 
-            flags.zero as u8, flags.negative as u8,
-            flags.carry as u8, flags.overflow as u8));
+        result.push_str(&format!("[Z={} N={} C={} V={} T={} RM={:?}]\n",
+            flags.zero as u8, flags.negative as u8, flags.carry as u8,
+            flags.overflow as u8, flags.in_trap as u8, flags.round_mode));
         
         result
     }
 }
 
+/// Rounding mode applied to the result of every `FADD`/`FSUB`/`FMUL`/`FDIV`,
+/// selected via the two-bit rounding field in `sr` (bits 5-6).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    Nearest,
+    TowardZero,
+    TowardPositive,
+    TowardNegative,
+}
+
+impl RoundingMode {
+    fn from_bits(bits: u16) -> Self {
+        match bits & 0b11 {
+            0 => RoundingMode::Nearest,
+            1 => RoundingMode::TowardZero,
+            2 => RoundingMode::TowardPositive,
+            _ => RoundingMode::TowardNegative,
+        }
+    }
+
+    fn to_bits(self) -> u16 {
+        match self {
+            RoundingMode::Nearest => 0,
+            RoundingMode::TowardZero => 1,
+            RoundingMode::TowardPositive => 2,
+            RoundingMode::TowardNegative => 3,
+        }
+    }
+
+    /// Round a double-precision intermediate result down to `f32` according
+    /// to this mode. Computing in `f64` first and rounding once here (rather
+    /// than doing the arithmetic directly in `f32`) keeps every mode honest
+    /// about which way it broke a tie.
+    pub fn round(self, value: f64) -> f32 {
+        let nearest = value as f32;
+        match self {
+            RoundingMode::Nearest => nearest,
+            RoundingMode::TowardZero => {
+                if (nearest as f64).abs() > value.abs() {
+                    step_f32(nearest, nearest < 0.0)
+                } else {
+                    nearest
+                }
+            }
+            RoundingMode::TowardPositive => {
+                if (nearest as f64) < value {
+                    step_f32(nearest, true)
+                } else {
+                    nearest
+                }
+            }
+            RoundingMode::TowardNegative => {
+                if (nearest as f64) > value {
+                    step_f32(nearest, false)
+                } else {
+                    nearest
+                }
+            }
+        }
+    }
+}
+
+/// Step `v` one ULP toward `+inf` (`up == true`) or `-inf` (`up == false`).
+fn step_f32(v: f32, up: bool) -> f32 {
+    if v.is_nan() || v.is_infinite() {
+        return v;
+    }
+    if v == 0.0 {
+        let tiny = f32::from_bits(1);
+        return if up { tiny } else { -tiny };
+    }
+    let bits = v.to_bits();
+    let stepped = if (v >= 0.0) == up { bits.wrapping_add(1) } else { bits.wrapping_sub(1) };
+    f32::from_bits(stepped)
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct StatusFlags {
     pub zero: bool,
     pub negative: bool,
     pub carry: bool,
     pub overflow: bool,
+    /// Set while a trap handler is running; cleared by `RTI`. Lets a
+    /// handler (or the VM) tell ordinary execution apart from trap context,
+    /// e.g. to avoid re-entering the handler for a nested trap.
+    pub in_trap: bool,
+    /// Rounding mode applied to floating-point arithmetic results.
+    pub round_mode: RoundingMode,
 }
 
 impl StatusFlags {
@@ -110,6 +243,8 @@ impl StatusFlags {
             negative: false,
             carry: false,
             overflow: false,
+            in_trap: false,
+            round_mode: RoundingMode::Nearest,
         }
     }
 
@@ -119,6 +254,8 @@ impl StatusFlags {
         if self.negative { sr |= 1 << 1; }
         if self.carry { sr |= 1 << 2; }
         if self.overflow { sr |= 1 << 3; }
+        if self.in_trap { sr |= 1 << 4; }
+        sr |= self.round_mode.to_bits() << 5;
         sr
     }
 
@@ -128,6 +265,8 @@ impl StatusFlags {
             negative: (sr & (1 << 1)) != 0,
             carry: (sr & (1 << 2)) != 0,
             overflow: (sr & (1 << 3)) != 0,
+            in_trap: (sr & (1 << 4)) != 0,
+            round_mode: RoundingMode::from_bits(sr >> 5),
         }
     }
 }
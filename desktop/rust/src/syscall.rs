@@ -0,0 +1,73 @@
+// ============================================================================
+// desktop/rust/src/syscall.rs
+// Pluggable host syscall interface for the SYSCALL special instruction
+// ============================================================================
+
+use crate::memory::{BulkReadError, Memory};
+use crate::registers::RegisterFile;
+use std::io::{self, Read, Write};
+
+/// Syscall number placed in R1 before `SYSCALL` executes.
+pub const SYS_EXIT: u16 = 0;
+pub const SYS_WRITE: u16 = 1;
+pub const SYS_READ: u16 = 2;
+
+/// Host-side handler for the `SYSCALL` instruction. `num` is read from R1;
+/// further arguments come from R2..R4. Implementations write any return
+/// value back into R1 themselves.
+pub trait SyscallHandler {
+    fn dispatch(&mut self, num: u16, regs: &mut RegisterFile, mem: &mut Memory) -> Result<(), String>;
+}
+
+/// The handler installed by default: EXIT (recognized but otherwise a
+/// no-op -- the VM itself halts after a successful `SYS_EXIT` dispatch),
+/// WRITE (R2 = buffer address, R3 = length; bytes are copied to stdout),
+/// and READ (R2 = buffer address, R3 = max length; bytes are read from
+/// stdin). Both WRITE and READ report the number of bytes transferred in R1.
+pub struct DefaultSyscallHandler;
+
+impl DefaultSyscallHandler {
+    pub fn new() -> Self {
+        DefaultSyscallHandler
+    }
+}
+
+impl SyscallHandler for DefaultSyscallHandler {
+    fn dispatch(&mut self, num: u16, regs: &mut RegisterFile, mem: &mut Memory) -> Result<(), String> {
+        match num {
+            SYS_EXIT => Ok(()),
+            SYS_WRITE => {
+                let addr = regs.read_gp(2) as usize;
+                let len = regs.read_gp(3) as usize;
+                // Most buffers are plain RAM, so try the zero-copy path
+                // first and only pay for an owned copy when the range
+                // isn't one contiguous run (e.g. it overlaps a device
+                // register).
+                let written = match mem.read_bytes(addr, len) {
+                    Ok(bytes) => {
+                        io::stdout().write_all(bytes).map_err(|e| e.to_string())?;
+                        bytes.len()
+                    }
+                    Err(BulkReadError::NotContiguous) => {
+                        let bytes = mem.read_bytes_copied(addr, len)?;
+                        io::stdout().write_all(&bytes).map_err(|e| e.to_string())?;
+                        bytes.len()
+                    }
+                    Err(BulkReadError::Fault(fault)) => return Err(fault.into()),
+                };
+                regs.write_gp(1, written as u16);
+                Ok(())
+            }
+            SYS_READ => {
+                let addr = regs.read_gp(2) as usize;
+                let max_len = regs.read_gp(3) as usize;
+                let mut buf = vec![0u8; max_len];
+                let read = io::stdin().read(&mut buf).map_err(|e| e.to_string())?;
+                mem.write_bytes(addr, &buf[..read])?;
+                regs.write_gp(1, read as u16);
+                Ok(())
+            }
+            _ => Err(format!("unknown syscall number {}", num)),
+        }
+    }
+}
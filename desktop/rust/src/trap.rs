@@ -0,0 +1,150 @@
+// ============================================================================
+// desktop/rust/src/trap.rs
+// Structured trap/exception subsystem for CVERE VM
+// ============================================================================
+
+use crate::memory::{Memory, MemoryFault};
+use std::fmt;
+
+/// Number of distinct trap classes, used to size the trap-vector table.
+pub const TRAP_CLASS_COUNT: usize = 11;
+
+/// A recoverable architectural event raised during instruction execution.
+///
+/// Unlike the old `Result<_, String>` errors, a `Trap` carries enough state
+/// for the VM to dispatch it through the trap-vector table and resume
+/// execution, rather than always unwinding out to the CLI.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trap {
+    /// Decoder produced a format/mnemonic the execute stage doesn't know.
+    InvalidOpcode { pc: u16, mnemonic: String },
+    /// A memory access was rejected; see `MemoryFault` for the reason.
+    MemoryFault { pc: u16, fault: MemoryFault },
+    /// Division by zero (reserved for future DIV-family instructions).
+    DivideByZero { pc: u16 },
+    /// Access to a page with no page-table entry.
+    UnmappedPage { pc: u16, addr: u16 },
+    /// Explicit breakpoint (reserved for a future BRK instruction).
+    Breakpoint { pc: u16 },
+    /// The programmable countdown timer device reached zero.
+    Timer { pc: u16 },
+    /// Raised by the `SYSCALL` instruction when no host handler is
+    /// installed or the installed handler reports an error.
+    Syscall { pc: u16 },
+    /// `PUSH` would move `sp` past the bottom of memory.
+    StackOverflow { pc: u16, sp: u16 },
+    /// `POP` was attempted with nothing left on the stack.
+    StackUnderflow { pc: u16, sp: u16 },
+    /// A program didn't fit in memory starting at its load address.
+    ProgramTooLarge { requested: usize, capacity: usize },
+    /// VM halted (informational; never dispatched through the vector table).
+    Halt,
+}
+
+impl Trap {
+    /// Index into the trap-vector table for this trap's class.
+    pub fn vector_index(&self) -> usize {
+        match self {
+            Trap::InvalidOpcode { .. } => 0,
+            Trap::MemoryFault { .. } => 1,
+            Trap::DivideByZero { .. } => 2,
+            Trap::UnmappedPage { .. } => 3,
+            Trap::Breakpoint { .. } => 4,
+            Trap::Timer { .. } => 5,
+            Trap::Syscall { .. } => 6,
+            Trap::StackOverflow { .. } => 7,
+            Trap::StackUnderflow { .. } => 8,
+            Trap::ProgramTooLarge { .. } => 9,
+            Trap::Halt => 10,
+        }
+    }
+
+    /// Short symbolic name, used by the CLI and disassembler output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Trap::InvalidOpcode { .. } => "InvalidOpcode",
+            Trap::MemoryFault { .. } => "MemoryFault",
+            Trap::DivideByZero { .. } => "DivideByZero",
+            Trap::UnmappedPage { .. } => "UnmappedPage",
+            Trap::Breakpoint { .. } => "Breakpoint",
+            Trap::Timer { .. } => "Timer",
+            Trap::Syscall { .. } => "Syscall",
+            Trap::StackOverflow { .. } => "StackOverflow",
+            Trap::StackUnderflow { .. } => "StackUnderflow",
+            Trap::ProgramTooLarge { .. } => "ProgramTooLarge",
+            Trap::Halt => "Halt",
+        }
+    }
+
+    /// PC at which the trap was raised, when applicable.
+    pub fn pc(&self) -> Option<u16> {
+        match self {
+            Trap::InvalidOpcode { pc, .. }
+            | Trap::MemoryFault { pc, .. }
+            | Trap::DivideByZero { pc }
+            | Trap::UnmappedPage { pc, .. }
+            | Trap::Breakpoint { pc }
+            | Trap::Timer { pc }
+            | Trap::Syscall { pc }
+            | Trap::StackOverflow { pc, .. }
+            | Trap::StackUnderflow { pc, .. } => Some(*pc),
+            Trap::ProgramTooLarge { .. } | Trap::Halt => None,
+        }
+    }
+
+    /// Render a fuller diagnostic than `Display`: the one-line summary,
+    /// the disassembled instruction at the faulting PC, and a
+    /// caret-annotated hex view of the memory surrounding it. Takes
+    /// `memory` separately because a `Trap` only carries what it needs to
+    /// dispatch through the vector table, not a handle to the machine that
+    /// raised it.
+    pub fn render(&self, memory: &Memory) -> String {
+        let mut out = format!("{}\n", self);
+
+        let pc = match self.pc() {
+            Some(pc) => pc,
+            None => return out,
+        };
+
+        if let Some(word) = memory.peek_word(pc as usize) {
+            out.push_str(&format!(
+                "  at {}\n",
+                crate::decoder::InstructionDecoder::disassemble(pc, word)
+            ));
+        }
+
+        let window_start = pc.saturating_sub(8) as usize;
+        out.push_str(&memory.hex_window(window_start, 16, pc as usize));
+        out
+    }
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trap::InvalidOpcode { pc, mnemonic } => {
+                write!(f, "{} at 0x{:04X}: unknown mnemonic '{}'", self.name(), pc, mnemonic)
+            }
+            Trap::MemoryFault { pc, fault } => {
+                write!(f, "{} at 0x{:04X}: {}", self.name(), pc, fault)
+            }
+            Trap::DivideByZero { pc } => write!(f, "{} at 0x{:04X}", self.name(), pc),
+            Trap::UnmappedPage { pc, addr } => {
+                write!(f, "{} at 0x{:04X}: address 0x{:04X} has no mapping", self.name(), pc, addr)
+            }
+            Trap::Breakpoint { pc } => write!(f, "{} at 0x{:04X}", self.name(), pc),
+            Trap::Timer { pc } => write!(f, "{} at 0x{:04X}", self.name(), pc),
+            Trap::Syscall { pc } => write!(f, "{} at 0x{:04X}", self.name(), pc),
+            Trap::StackOverflow { pc, sp } => {
+                write!(f, "{} at 0x{:04X}: sp 0x{:04X} ran off the bottom of memory", self.name(), pc, sp)
+            }
+            Trap::StackUnderflow { pc, sp } => {
+                write!(f, "{} at 0x{:04X}: POP with empty stack (sp 0x{:04X})", self.name(), pc, sp)
+            }
+            Trap::ProgramTooLarge { requested, capacity } => {
+                write!(f, "{}: program needs {} bytes but memory is only {} bytes", self.name(), requested, capacity)
+            }
+            Trap::Halt => write!(f, "{}", self.name()),
+        }
+    }
+}